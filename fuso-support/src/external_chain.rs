@@ -12,15 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use codec::{Codec, Decode, Encode};
+use codec::{Decode, Encode};
+#[cfg(feature = "std")]
 use sp_core::hexdisplay::HexDisplay;
 use sp_runtime::RuntimeDebug;
-use sp_std::{
-    convert::TryFrom,
-    fmt::{Debug, Display, Formatter},
-    vec::Vec,
-};
+#[cfg(feature = "std")]
+use sp_std::fmt::Display;
+use sp_std::{convert::TryFrom, fmt::Debug, vec::Vec};
 
+/// The variants are named after the tickers of the chains.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug)]
 pub enum ExternalChain {
     BTC,
@@ -72,7 +73,7 @@ impl TryFrom<(ExternalChain, Vec<u8>)> for ExternalChainAddress {
     type Error = AddressError;
 
     // TODO
-    fn try_from((chain, encoded_addr): (ExternalChain, Vec<u8>)) -> Result<Self, AddressError> {
+    fn try_from((chain, _encoded_addr): (ExternalChain, Vec<u8>)) -> Result<Self, AddressError> {
         match chain {
             ExternalChain::BTC => Err(AddressError::IllegalSs58),
             ExternalChain::ETH | ExternalChain::ERC20(_) => Err(AddressError::IllegalKeccak256),
@@ -49,6 +49,23 @@ pub trait ReservableToken<TokenId, AccountId> {
     ) -> DispatchResult;
 }
 
+/// Prices of tokens in TAO, i.e. the TAO of one unit of the token scaled by 10^18.
+pub trait PriceProvider<TokenId> {
+    /// `None` if the price is unknown or stale.
+    fn price(token: &TokenId) -> Option<u128>;
+
+    /// The latest price however old it is, `None` if the token has never been priced.
+    fn last_price(token: &TokenId) -> Option<u128> {
+        Self::price(token)
+    }
+}
+
+impl<TokenId> PriceProvider<TokenId> for () {
+    fn price(_token: &TokenId) -> Option<u128> {
+        None
+    }
+}
+
 pub trait ProofOfSecurity<AccountId> {
     type ExternalChainAddress: Parameter
         + Member
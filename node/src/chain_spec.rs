@@ -1,6 +1,7 @@
 use fuso_runtime::{
     opaque::SessionKeys, AccountId, AuraConfig, BalancesConfig, CouncilConfig, FoundationConfig,
-    GenesisConfig, GrandpaConfig, SessionConfig, Signature, SudoConfig, SystemConfig, WASM_BINARY,
+    GenesisConfig, GrandpaConfig, ReceiptsConfig, SessionConfig, Signature, SudoConfig,
+    SystemConfig, TokenConfig, WASM_BINARY,
 };
 use sc_service::ChainType;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
@@ -165,5 +166,7 @@ fn testnet_genesis(
                 ),
             ],
         }),
+        fuso_pallet_receipts: Some(ReceiptsConfig::default()),
+        fuso_pallet_token: Some(TokenConfig::default()),
     }
 }
@@ -7,7 +7,7 @@
 
 use std::sync::Arc;
 
-use fuso_runtime::{opaque::Block, AccountId, Balance, Index};
+use fuso_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Index, TokenId};
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::{Error as BlockChainError, HeaderMetadata, HeaderBackend};
 use sp_block_builder::BlockBuilder;
@@ -34,11 +34,15 @@ pub fn create_full<C, P>(
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: fuso_pallet_receipts_rpc::ReceiptsRuntimeApi<Block, AccountId, TokenId, BlockNumber>,
+	C::Api: fuso_pallet_token_rpc::TokenRuntimeApi<Block, TokenId>,
 	C::Api: BlockBuilder<Block>,
 	P: TransactionPool + 'static,
 {
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use fuso_pallet_receipts_rpc::{Receipts, ReceiptsApi};
+	use fuso_pallet_token_rpc::{Token, TokenApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 	let FullDeps {
@@ -55,6 +59,14 @@ pub fn create_full<C, P>(
 		TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
 	);
 
+	io.extend_with(
+		ReceiptsApi::to_delegate(Receipts::new(client.clone()))
+	);
+
+	io.extend_with(
+		TokenApi::to_delegate(Token::new(client.clone()))
+	);
+
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
 	// to call into the runtime.
@@ -16,7 +16,7 @@
 use frame_support::{
     decl_error, decl_event, decl_module, decl_storage,
     traits::Get,
-    traits::{LockIdentifier, LockableCurrency, WithdrawReasons},
+    traits::{Contains, LockIdentifier, LockableCurrency, WithdrawReasons},
     weights::Weight,
 };
 use frame_system::ensure_root;
@@ -139,10 +139,10 @@ impl<T: Trait> Module<T> {
         start_council.saturating_add(index.saturating_mul(council_term))
     }
 
-    fn initialize_validators(validators: &Vec<T::AccountId>) {
+    fn initialize_validators(validators: &[T::AccountId]) {
         let init = validators
             .iter()
-            .map(|x| x.clone())
+            .cloned()
             .collect::<BTreeSet<T::AccountId>>();
         Validators::<T>::put(&init);
     }
@@ -191,7 +191,7 @@ impl<T: Trait> Module<T> {
                         .collect::<BTreeSet<T::AccountId>>();
                     return Some(validators);
                 }
-            } else if voter_members.len() > 0 {
+            } else if !voter_members.is_empty() {
                 // unlock all ledger currency
                 for i in voter_members.iter() {
                     Self::unlock_pledger_currency(&i.pledger, true);
@@ -227,7 +227,7 @@ impl<T: Trait> Module<T> {
 
     // unlock pledger currency
     fn unlock_pledger_currency(
-        pledger: &Vec<Pledger<T::AccountId, T::BlockNumber, BalanceOf<T>>>,
+        pledger: &[Pledger<T::AccountId, T::BlockNumber, BalanceOf<T>>],
         is_unlock_all: bool,
     ) {
         for j in pledger.iter() {
@@ -268,15 +268,15 @@ impl<T: Trait> pallet_session::ShouldEndSession<T::BlockNumber> for Module<T> {
 }
 
 impl<T: Trait> pallet_session::SessionManager<T::AccountId> for Module<T> {
-    fn new_session(new_index: u32) -> Option<Vec<T::AccountId>> {
+    fn new_session(_new_index: u32) -> Option<Vec<T::AccountId>> {
         // set validators from vote pallet
         let validators = Self::validators().iter().cloned().collect();
         Some(validators)
     }
 
-    fn end_session(end_index: u32) {}
+    fn end_session(_end_index: u32) {}
 
-    fn start_session(start_index: u32) {}
+    fn start_session(_start_index: u32) {}
 }
 
 impl<T: Trait> frame_support::traits::EstimateNextSessionRotation<T::BlockNumber> for Module<T> {
@@ -290,6 +290,16 @@ impl<T: Trait> frame_support::traits::EstimateNextSessionRotation<T::BlockNumber
     }
 }
 
+impl<T: Trait> Contains<T::AccountId> for Module<T> {
+    fn sorted_members() -> Vec<T::AccountId> {
+        Self::validators().into_iter().collect()
+    }
+
+    fn contains(who: &T::AccountId) -> bool {
+        Self::validators().contains(who)
+    }
+}
+
 pub struct ValidatorOf<T>(sp_std::marker::PhantomData<T>);
 
 impl<T: Trait> Convert<T::AccountId, Option<T::AccountId>> for ValidatorOf<T> {
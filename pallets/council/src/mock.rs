@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+// `impl_outer_origin` implements `Into` rather than `From`
+#![allow(clippy::from_over_into)]
+
 use crate::*;
 use crate::{Module, Trait};
 use frame_support::traits::{OnFinalize, OnInitialize};
@@ -193,8 +196,7 @@ pub fn council_test_ext() -> sp_io::TestExternalities {
 
     let mut t = system::GenesisConfig::default()
         .build_storage::<Test>()
-        .unwrap()
-        .into();
+        .unwrap();
 
     balances::GenesisConfig::<Test> {
         balances: vec![
@@ -212,7 +214,7 @@ pub fn council_test_ext() -> sp_io::TestExternalities {
     crate::GenesisConfig::<Test> {
         validators: initial_authorities
             .iter()
-            .map(|x| x.clone())
+            .copied()
             .collect::<Vec<_>>(),
     }
     .assimilate_storage(&mut t)
@@ -298,18 +298,15 @@ impl<T: Trait> Module<T> {
         let pledger = Pledger {
             account: sender.clone(),
             block_number: <frame_system::Module<T>>::block_number(),
-            amount: amount.clone(),
+            amount,
         };
 
-        // new vec
-        let mut pledger_vec: Vec<Pledger<T::AccountId, T::BlockNumber, BalanceOf<T>>> = Vec::new();
-        // push pledger
-        pledger_vec.push(pledger);
+        let pledger_vec: Vec<Pledger<T::AccountId, T::BlockNumber, BalanceOf<T>>> = vec![pledger];
 
         let voter_member = Voter {
             account: voter.clone(),
             round: Self::vote_round_count(),
-            amount: amount.clone(),
+            amount,
             pledger: pledger_vec,
         };
 
@@ -364,7 +361,7 @@ impl<T: Trait> Module<T> {
                     data.pledger.push(Pledger {
                         account: sender.clone(),
                         block_number: <frame_system::Module<T>>::block_number(),
-                        amount: amount,
+                        amount,
                     });
                 }
             }
@@ -437,7 +434,7 @@ impl<T: Trait> Referendum<T::BlockNumber, T::VoteIndex, VoterOf<T>> for Module<T
             let heap = Self::voter_members();
             let mut vec = heap.into_sorted_vec();
             vec.reverse();
-            return Some(vec);
+            Some(vec)
         } else {
             None
         }
@@ -15,7 +15,6 @@
 use super::*;
 use crate::{mock::*, Error};
 use frame_support::{assert_noop, assert_ok};
-use sp_std::vec::Vec;
 
 // test elections
 #[test]
@@ -45,8 +44,7 @@ fn test_add_candidate() {
         assert_eq!(round, 1);
 
         assert_ok!(ElectionsModule::add_candidate(Origin::signed(ALICE), BOB));
-        let mut vec = Vec::new();
-        vec.push(BOB);
+        let vec = vec![BOB];
         assert_eq!(ElectionsModule::candidates(), vec);
 
         // set bob again
@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+// `impl_outer_origin` implements `Into` rather than `From`
+#![allow(clippy::from_over_into)]
+
 use crate::*;
 use crate::{Module, Trait};
 use frame_support::traits::{OnFinalize, OnInitialize, ReservableCurrency};
@@ -129,8 +132,7 @@ pub fn run_to_block(block: u64) {
 pub fn foundation_test_ext() -> sp_io::TestExternalities {
     let mut t = system::GenesisConfig::default()
         .build_storage::<Test>()
-        .unwrap()
-        .into();
+        .unwrap();
 
     balances::GenesisConfig::<Test> {
         balances: vec![
@@ -15,7 +15,6 @@
 use crate::mock::*;
 use crate::*;
 use frame_support::assert_ok;
-use frame_support::dispatch::DispatchResult;
 
 // test foundation
 #[test]
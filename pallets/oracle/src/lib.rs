@@ -0,0 +1,210 @@
+// Copyright 2021 UINB Technologies Pte. Ltd.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+use codec::Codec;
+use frame_support::{
+    decl_error, decl_event, decl_module, decl_storage, ensure,
+    storage::IterableStorageDoubleMap,
+    traits::{Contains, Get},
+    weights::Weight,
+    Parameter,
+};
+use frame_system::ensure_signed;
+use fuso_support::traits::PriceProvider;
+use sp_runtime::traits::{
+    AtLeast32BitUnsigned, MaybeSerializeDeserialize, Member, Saturating, Zero,
+};
+use sp_std::{
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+    fmt::Debug,
+    prelude::*,
+};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub trait Trait: frame_system::Trait {
+    type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+    type TokenId: Member
+        + Parameter
+        + AtLeast32BitUnsigned
+        + Default
+        + Copy
+        + Codec
+        + Debug
+        + MaybeSerializeDeserialize;
+
+    /// Accounts allowed to feed prices, e.g. the validators of the council.
+    type Feeders: Contains<Self::AccountId>;
+
+    /// The feeds are aggregated every `AggregationWindow` blocks.
+    type AggregationWindow: Get<Self::BlockNumber>;
+
+    /// Feeds and prices older than `StaleAfter` blocks are ignored.
+    type StaleAfter: Get<Self::BlockNumber>;
+
+    /// The minimum fresh feeds of a token to update its price.
+    type MinFeeds: Get<u32>;
+
+    /// The most tokens fed at the same time, so aggregating them stays bounded.
+    type MaxTokens: Get<u32>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Oracle {
+        /// The latest price of each feeder with the block it was fed.
+        Feeds get(fn feeds): double_map
+            hasher(twox_64_concat) T::TokenId,
+            hasher(blake2_128_concat) T::AccountId
+        => Option<(u128, T::BlockNumber)>;
+
+        /// The median of the fresh feeds with the block it was aggregated.
+        Prices get(fn prices): map hasher(twox_64_concat) T::TokenId => Option<(u128, T::BlockNumber)>;
+
+        /// The tokens with feeds, up to `MaxTokens`.
+        Tokens get(fn tokens): BTreeSet<T::TokenId>;
+    }
+}
+
+decl_event! {
+    pub enum Event<T>
+    where
+        <T as frame_system::Trait>::AccountId,
+        TokenId = <T as Trait>::TokenId,
+    {
+        PriceFed(AccountId, TokenId, u128),
+        PriceUpdated(TokenId, u128),
+    }
+}
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        NotFeeder,
+        InvalidPrice,
+        TooManyTokens,
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+
+        const AggregationWindow: T::BlockNumber = T::AggregationWindow::get();
+
+        const StaleAfter: T::BlockNumber = T::StaleAfter::get();
+
+        const MinFeeds: u32 = T::MinFeeds::get();
+
+        const MaxTokens: u32 = T::MaxTokens::get();
+
+        fn deposit_event() = default;
+
+        /// Feed the TAO of one unit of the token scaled by 10^18.
+        #[weight = 100_000]
+        pub fn feed(origin, token: T::TokenId, price: u128) {
+            let feeder = ensure_signed(origin)?;
+            ensure!(T::Feeders::contains(&feeder), Error::<T>::NotFeeder);
+            ensure!(price > 0, Error::<T>::InvalidPrice);
+            Tokens::<T>::try_mutate(|tokens| -> Result<(), Error<T>> {
+                if !tokens.contains(&token) {
+                    ensure!((tokens.len() as u32) < T::MaxTokens::get(), Error::<T>::TooManyTokens);
+                    tokens.insert(token);
+                }
+                Ok(())
+            })?;
+            let now = frame_system::Module::<T>::block_number();
+            Feeds::<T>::insert(&token, &feeder, (price, now));
+            Self::deposit_event(RawEvent::PriceFed(feeder, token, price));
+        }
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            let window = T::AggregationWindow::get();
+            if window.is_zero() || !(now % window).is_zero() {
+                return 0;
+            }
+            Self::aggregate(now)
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Update the price of each token to the median of its fresh feeds, feeds that are stale or
+    /// from accounts no longer feeders are dropped, and so are the tokens without feeds left.
+    fn aggregate(now: T::BlockNumber) -> Weight {
+        let stale_after = T::StaleAfter::get();
+        let mut tokens = Tokens::<T>::get();
+        let mut fresh = BTreeMap::<T::TokenId, Vec<u128>>::new();
+        let mut stale = Vec::new();
+        let mut reads = 1u64;
+        for token in tokens.iter() {
+            for (feeder, (price, at)) in Feeds::<T>::iter_prefix(token) {
+                reads = reads.saturating_add(1);
+                if now.saturating_sub(at) > stale_after || !T::Feeders::contains(&feeder) {
+                    stale.push((*token, feeder));
+                } else {
+                    fresh.entry(*token).or_insert_with(Vec::new).push(price);
+                }
+            }
+        }
+        let mut writes = stale.len() as u64;
+        for (token, feeder) in stale {
+            Feeds::<T>::remove(&token, &feeder);
+        }
+        let count = tokens.len();
+        tokens = tokens.into_iter().filter(|token| fresh.contains_key(token)).collect();
+        if tokens.len() < count {
+            Tokens::<T>::put(tokens);
+            writes = writes.saturating_add(1);
+        }
+        for (token, mut prices) in fresh {
+            if (prices.len() as u32) < T::MinFeeds::get() {
+                continue;
+            }
+            let price = Self::median(&mut prices);
+            Prices::<T>::insert(&token, (price, now));
+            writes = writes.saturating_add(1);
+            Self::deposit_event(RawEvent::PriceUpdated(token, price));
+        }
+        T::DbWeight::get().reads_writes(reads, writes)
+    }
+
+    fn median(prices: &mut Vec<u128>) -> u128 {
+        prices.sort_unstable();
+        let mid = prices.len() / 2;
+        if prices.len() % 2 == 1 {
+            prices[mid]
+        } else {
+            let (a, b) = (prices[mid - 1], prices[mid]);
+            a / 2 + b / 2 + (a % 2 + b % 2) / 2
+        }
+    }
+}
+
+impl<T: Trait> PriceProvider<T::TokenId> for Module<T> {
+    fn price(token: &T::TokenId) -> Option<u128> {
+        let now = frame_system::Module::<T>::block_number();
+        Prices::<T>::get(token)
+            .filter(|(_, at)| now.saturating_sub(*at) <= T::StaleAfter::get())
+            .map(|(price, _)| price)
+    }
+
+    fn last_price(token: &T::TokenId) -> Option<u128> {
+        Prices::<T>::get(token).map(|(price, _)| price)
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright 2021 UINB Technologies Pte. Ltd.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `impl_outer_origin` implements `Into` rather than `From`
+#![allow(clippy::from_over_into)]
+
+use crate::{Module, Trait};
+use frame_support::traits::{Contains, OnFinalize, OnInitialize};
+use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
+use frame_system as system;
+use sp_core::H256;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+    Perbill,
+};
+
+pub const ALICE: <Test as system::Trait>::AccountId = 1;
+pub const BOB: <Test as system::Trait>::AccountId = 2;
+pub const CHRIS: <Test as system::Trait>::AccountId = 3;
+pub const DAVE: <Test as system::Trait>::AccountId = 4;
+
+impl_outer_origin! {
+    pub enum Origin for Test where system = frame_system {}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const MaximumBlockWeight: Weight = 1024;
+    pub const MaximumBlockLength: u32 = 2 * 1024;
+    pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+    pub const AggregationWindow: u64 = 5;
+    pub const StaleAfter: u64 = 10;
+    pub const MinFeeds: u32 = 2;
+    pub const MaxTokens: u32 = 2;
+}
+
+impl system::Trait for Test {
+    type BaseCallFilter = ();
+    type Origin = Origin;
+    type Call = ();
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+    type BlockHashCount = BlockHashCount;
+    type MaximumBlockWeight = MaximumBlockWeight;
+    type DbWeight = ();
+    type BlockExecutionWeight = ();
+    type ExtrinsicBaseWeight = ();
+    type MaximumExtrinsicWeight = MaximumBlockWeight;
+    type MaximumBlockLength = MaximumBlockLength;
+    type AvailableBlockRatio = AvailableBlockRatio;
+    type Version = ();
+    type PalletInfo = ();
+    type AccountData = ();
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+}
+
+/// ALICE, BOB and CHRIS feed the prices.
+pub struct Feeders;
+
+impl Contains<u64> for Feeders {
+    fn sorted_members() -> Vec<u64> {
+        vec![ALICE, BOB, CHRIS]
+    }
+}
+
+impl Trait for Test {
+    type Event = ();
+    type TokenId = u32;
+    type Feeders = Feeders;
+    type AggregationWindow = AggregationWindow;
+    type StaleAfter = StaleAfter;
+    type MinFeeds = MinFeeds;
+    type MaxTokens = MaxTokens;
+}
+
+pub type OracleModule = Module<Test>;
+pub type System = frame_system::Module<Test>;
+
+pub fn run_to_block(block: u64) {
+    while System::block_number() < block {
+        OracleModule::on_finalize(System::block_number());
+        System::on_finalize(System::block_number());
+        System::set_block_number(System::block_number() + 1);
+        System::on_initialize(System::block_number());
+        OracleModule::on_initialize(System::block_number());
+    }
+}
+
+pub fn oracle_test_ext() -> sp_io::TestExternalities {
+    let t = system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    let mut ext = sp_io::TestExternalities::new(t);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
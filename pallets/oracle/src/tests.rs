@@ -0,0 +1,97 @@
+// Copyright 2021 UINB Technologies Pte. Ltd.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::mock::*;
+use crate::*;
+use frame_support::{assert_noop, assert_ok};
+
+#[test]
+fn test_feed_price() {
+    oracle_test_ext().execute_with(|| {
+        assert_noop!(
+            OracleModule::feed(Origin::signed(DAVE), 1, 100),
+            Error::<Test>::NotFeeder
+        );
+        assert_noop!(
+            OracleModule::feed(Origin::signed(ALICE), 1, 0),
+            Error::<Test>::InvalidPrice
+        );
+        assert_ok!(OracleModule::feed(Origin::signed(ALICE), 1, 100));
+        assert_ok!(OracleModule::feed(Origin::signed(BOB), 1, 300));
+        assert_ok!(OracleModule::feed(Origin::signed(CHRIS), 1, 200));
+        // a single feed isn't enough
+        assert_ok!(OracleModule::feed(Origin::signed(ALICE), 2, 100));
+        assert_eq!(OracleModule::feeds(1, ALICE), Some((100, 1)));
+        assert_eq!(<OracleModule as PriceProvider<u32>>::price(&1), None);
+
+        run_to_block(4);
+        assert_eq!(OracleModule::prices(1), None);
+        run_to_block(5);
+        assert_eq!(OracleModule::prices(1), Some((200, 5)));
+        assert_eq!(<OracleModule as PriceProvider<u32>>::price(&1), Some(200));
+        assert_eq!(OracleModule::prices(2), None);
+
+        // the median of two is the mean
+        assert_ok!(OracleModule::feed(Origin::signed(BOB), 2, 301));
+        run_to_block(10);
+        assert_eq!(<OracleModule as PriceProvider<u32>>::price(&2), Some(200));
+    });
+}
+
+#[test]
+fn test_stale_price() {
+    oracle_test_ext().execute_with(|| {
+        assert_ok!(OracleModule::feed(Origin::signed(ALICE), 1, 100));
+        assert_ok!(OracleModule::feed(Origin::signed(BOB), 1, 300));
+        run_to_block(10);
+        assert_eq!(OracleModule::prices(1), Some((200, 10)));
+
+        // the feeds are dropped but the price lasts until it is stale as well
+        run_to_block(15);
+        assert_eq!(OracleModule::feeds(1, ALICE), None);
+        assert_eq!(OracleModule::prices(1), Some((200, 10)));
+        assert_eq!(<OracleModule as PriceProvider<u32>>::price(&1), Some(200));
+        run_to_block(21);
+        assert_eq!(<OracleModule as PriceProvider<u32>>::price(&1), None);
+        assert_eq!(<OracleModule as PriceProvider<u32>>::last_price(&1), Some(200));
+        assert_eq!(<OracleModule as PriceProvider<u32>>::last_price(&2), None);
+
+        assert_ok!(OracleModule::feed(Origin::signed(ALICE), 1, 110));
+        assert_ok!(OracleModule::feed(Origin::signed(CHRIS), 1, 130));
+        run_to_block(25);
+        assert_eq!(<OracleModule as PriceProvider<u32>>::price(&1), Some(120));
+    });
+}
+
+#[test]
+fn test_max_tokens() {
+    oracle_test_ext().execute_with(|| {
+        assert_ok!(OracleModule::feed(Origin::signed(ALICE), 1, 100));
+        assert_ok!(OracleModule::feed(Origin::signed(ALICE), 2, 100));
+        assert_noop!(
+            OracleModule::feed(Origin::signed(BOB), 3, 100),
+            Error::<Test>::TooManyTokens
+        );
+        // a tracked token can always be fed
+        assert_ok!(OracleModule::feed(Origin::signed(BOB), 1, 300));
+        assert_eq!(OracleModule::tokens().len(), 2);
+
+        // token 2 is dropped once its only feed is stale
+        run_to_block(10);
+        assert_ok!(OracleModule::feed(Origin::signed(ALICE), 1, 100));
+        run_to_block(15);
+        assert_eq!(OracleModule::tokens().into_iter().collect::<Vec<_>>(), vec![1]);
+        assert_ok!(OracleModule::feed(Origin::signed(BOB), 3, 100));
+    });
+}
@@ -0,0 +1,44 @@
+// Copyright 2021 UINB Technologies Pte. Ltd.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime API definition for the receipts pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// the lints are raised by the code `decl_runtime_apis` generates
+#![allow(clippy::too_many_arguments, clippy::unnecessary_mut_passed)]
+
+use codec::Codec;
+pub use fuso_pallet_receipts::{
+    AssetId, Balance, Dominator, DominatorStatus, LedgerEntry, Receipt, ReceiptStatus,
+};
+use sp_std::prelude::*;
+
+sp_api::decl_runtime_apis! {
+    pub trait ReceiptsApi<AccountId, TokenId, BlockNumber> where
+        AccountId: Codec,
+        TokenId: Codec,
+        BlockNumber: Codec,
+    {
+        /// All receipts of the fund owner as `(dominator, asset, receipt)`.
+        fn receipts_of(fund_owner: AccountId) -> Vec<(AccountId, AssetId<TokenId>, Receipt<BlockNumber>)>;
+
+        /// The assets hosted by the dominator as `(fund_owner, tao, tokens)`.
+        fn hosted_by(dominator: AccountId) -> Vec<LedgerEntry<AccountId, TokenId>>;
+
+        fn dominator(dominator: AccountId) -> Option<Dominator<BlockNumber>>;
+
+        /// The TAO available in the insurance pot.
+        fn insurance_pot() -> Balance;
+    }
+}
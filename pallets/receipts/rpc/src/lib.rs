@@ -0,0 +1,174 @@
+// Copyright 2021 UINB Technologies Pte. Ltd.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! RPC interface for the receipts pallet, balances are rendered as decimal strings since they
+//! don't fit into a JSON number.
+
+use codec::Codec;
+pub use fuso_pallet_receipts_rpc_runtime_api::ReceiptsApi as ReceiptsRuntimeApi;
+use fuso_pallet_receipts_rpc_runtime_api::{AssetId, DominatorStatus, ReceiptStatus};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptInfo<AccountId, TokenId, BlockNumber> {
+    pub dominator: AccountId,
+    pub asset: AssetId<TokenId>,
+    pub amount: String,
+    pub status: ReceiptStatus<BlockNumber>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostingInfo<AccountId, TokenId> {
+    pub fund_owner: AccountId,
+    pub tao: String,
+    pub tokens: Vec<(TokenId, String)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DominatorInfo<BlockNumber> {
+    pub pledged: String,
+    pub status: DominatorStatus<BlockNumber>,
+}
+
+#[rpc]
+pub trait ReceiptsApi<BlockHash, AccountId, TokenId, BlockNumber> {
+    #[rpc(name = "fuso_receipts_receiptsOf")]
+    fn receipts_of(
+        &self,
+        fund_owner: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<ReceiptInfo<AccountId, TokenId, BlockNumber>>>;
+
+    #[rpc(name = "fuso_receipts_hostedBy")]
+    fn hosted_by(
+        &self,
+        dominator: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<HostingInfo<AccountId, TokenId>>>;
+
+    #[rpc(name = "fuso_receipts_dominator")]
+    fn dominator(
+        &self,
+        dominator: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Option<DominatorInfo<BlockNumber>>>;
+
+    #[rpc(name = "fuso_receipts_insurancePot")]
+    fn insurance_pot(&self, at: Option<BlockHash>) -> Result<String>;
+}
+
+pub struct Receipts<C, Block> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<Block>,
+}
+
+impl<C, Block> Receipts<C, Block> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+const RUNTIME_ERROR: i64 = 1;
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(RUNTIME_ERROR),
+        message: "Unable to query receipts.".into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+impl<C, Block, AccountId, TokenId, BlockNumber>
+    ReceiptsApi<<Block as BlockT>::Hash, AccountId, TokenId, BlockNumber> for Receipts<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: ReceiptsRuntimeApi<Block, AccountId, TokenId, BlockNumber>,
+    AccountId: Codec,
+    TokenId: Codec,
+    BlockNumber: Codec,
+{
+    fn receipts_of(
+        &self,
+        fund_owner: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<ReceiptInfo<AccountId, TokenId, BlockNumber>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        let receipts = api.receipts_of(&at, fund_owner).map_err(runtime_error)?;
+        Ok(receipts
+            .into_iter()
+            .map(|(dominator, asset, r)| ReceiptInfo {
+                dominator,
+                asset,
+                amount: r.value.to_string(),
+                status: r.status,
+            })
+            .collect())
+    }
+
+    fn hosted_by(
+        &self,
+        dominator: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<HostingInfo<AccountId, TokenId>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        let hosted = api.hosted_by(&at, dominator).map_err(runtime_error)?;
+        Ok(hosted
+            .into_iter()
+            .map(|(fund_owner, tao, tokens)| HostingInfo {
+                fund_owner,
+                tao: tao.to_string(),
+                tokens: tokens
+                    .into_iter()
+                    .map(|(id, v)| (id, v.to_string()))
+                    .collect(),
+            })
+            .collect())
+    }
+
+    fn dominator(
+        &self,
+        dominator: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<DominatorInfo<BlockNumber>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        let claimed = api.dominator(&at, dominator).map_err(runtime_error)?;
+        Ok(claimed.map(|d| DominatorInfo {
+            pledged: d.pledged.to_string(),
+            status: d.status,
+        }))
+    }
+
+    fn insurance_pot(&self, at: Option<<Block as BlockT>::Hash>) -> Result<String> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        let pot = api.insurance_pot(&at).map_err(runtime_error)?;
+        Ok(pot.to_string())
+    }
+}
@@ -17,19 +17,41 @@
 use codec::{Codec, Decode, Encode};
 use frame_support::{
     decl_error, decl_event, decl_module, decl_storage, ensure,
-    traits::{BalanceStatus, Currency, LockableCurrency, ReservableCurrency},
-    Parameter,
+    storage::{
+        migration::StorageIterator,
+        IterableStorageDoubleMap, IterableStorageMap,
+    },
+    weights::Weight,
+    traits::{
+        BalanceStatus, Currency, EnsureOrigin, ExistenceRequirement, Get, Imbalance,
+        LockableCurrency, OnUnbalanced, ReservableCurrency, WithdrawReason,
+    },
+    transactional, Parameter,
 };
 use frame_system::ensure_signed;
-use fuso_support::{external_chain::*, traits::ReservableToken};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use fuso_support::traits::{PriceProvider, ReservableToken};
 use sp_runtime::{
     traits::{
-        AtLeast32BitUnsigned, CheckEqual, CheckedAdd, CheckedSub, MaybeDisplay, MaybeMallocSizeOf,
-        MaybeSerializeDeserialize, Member, Saturating, SimpleBitOps, StaticLookup, Zero,
+        AccountIdConversion, Hash, MaybeDisplay, MaybeSerializeDeserialize, Member, One,
+        SaturatedConversion, Saturating, SimpleBitOps, StaticLookup, Zero,
     },
-    DispatchResult, RuntimeDebug,
+    helpers_128bit::multiply_by_rational,
+    DispatchError, DispatchResult, ModuleId, Perbill, RuntimeDebug,
+};
+use sp_std::{
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+    convert::TryInto,
+    prelude::*,
+    vec::Vec,
 };
-use sp_std::{convert::TryInto, fmt::Debug, prelude::*, vec::Vec};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
 
 pub type BalanceOf<T> =
     <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
@@ -50,95 +72,183 @@ pub type HostingPair<T> = (
     <T as frame_system::Trait>::AccountId,
 );
 
+#[allow(clippy::upper_case_acronyms)]
 pub type UID = u128;
 
 pub type Balance = u128;
 
+/// Prices are fixed-point numbers with 18 decimals, i.e. the quote of a trade is
+/// `price * quantity / PRICE_UNIT`.
+pub const PRICE_UNIT: Balance = 1_000_000_000_000_000_000;
+
+/// The asset a receipt is issued for, either the native TAO or a token.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum AssetId<TokenId> {
+    Tao,
+    Token(TokenId),
+}
+
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
-pub enum Receipt<TokenId, BlockNumber> {
-    Tao(Balance, ReceiptStatus<BlockNumber>),
-    Token(TokenId, Balance, ReceiptStatus<BlockNumber>),
+pub struct Receipt<BlockNumber> {
+    pub value: Balance,
+    pub status: ReceiptStatus<BlockNumber>,
 }
 
-impl<X: Eq + PartialEq, Y> Receipt<X, Y> {
-    fn is_active(&self) -> bool {
-        match self {
-            Self::Tao(_, status) => matches!(status, ReceiptStatus::Active),
-            Self::Token(_, _, status) => matches!(status, ReceiptStatus::Active),
-        }
-    }
-    fn is_revoking(&self) -> bool {
-        match self {
-            Self::Tao(_, status) => matches!(status, ReceiptStatus::Revoking(_)),
-            Self::Token(_, _, status) => matches!(status, ReceiptStatus::Revoking(_)),
+impl<BlockNumber> Receipt<BlockNumber> {
+    fn new(value: Balance) -> Self {
+        Self {
+            value,
+            status: ReceiptStatus::Active,
         }
     }
 
-    fn get_value(&self) -> Balance {
-        match self {
-            Self::Tao(v, _) => *v,
-            Self::Token(_, v, _) => *v,
-        }
+    fn is_active(&self) -> bool {
+        matches!(self.status, ReceiptStatus::Active)
     }
 
-    fn is_token_of(&self, token_id: &X) -> bool {
-        match self {
-            Self::Tao(_, _) => false,
-            Self::Token(id, _, _) => id == token_id,
-        }
+    fn is_revoking(&self) -> bool {
+        matches!(self.status, ReceiptStatus::Revoking(_))
     }
 
-    fn is_same_assets(&self, other: &Self) -> bool {
-        match self {
-            Self::Tao(_, _) => matches!(other, Self::Tao(_, _)),
-            Self::Token(id, _, _) => other.is_token_of(id),
+    fn revoking_since(&self) -> Option<&BlockNumber> {
+        match &self.status {
+            ReceiptStatus::Revoking(t) => Some(t),
+            _ => None,
         }
     }
 }
 
-impl<X: Eq + PartialEq, Y> Saturating for Receipt<X, Y> {
-    fn saturating_add(self, other: Self) -> Self {
-        let new = self.get_value().saturating_add(other.get_value());
-        match self {
-            Self::Tao(_, status) => Self::Tao(new, status),
-            Self::Token(id, _, status) => Self::Token(id, new, status),
-        }
-    }
+#[derive(PartialEq, Eq, Copy, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum Side {
+    Buy,
+    Sell,
+}
 
-    fn saturating_sub(self, other: Self) -> Self {
-        let new = self.get_value().saturating_sub(other.get_value());
-        match self {
-            Self::Tao(_, status) => Self::Tao(new, status),
-            Self::Token(id, _, status) => Self::Token(id, new, status),
-        }
-    }
+/// A fill matched by the dominator, `side` is the one of the taker and the `fee` is charged on
+/// what the taker receives.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct Trade<AccountId, TokenId> {
+    pub maker: AccountId,
+    pub taker: AccountId,
+    pub base: AssetId<TokenId>,
+    pub quote: AssetId<TokenId>,
+    pub side: Side,
+    pub price: Balance,
+    pub quantity: Balance,
+    pub fee: Balance,
+}
 
-    fn saturating_mul(self, _: Self) -> Self {
-        unimplemented!();
-    }
+/// Receipts of a hosting pair used to be stored together as `Vec<LegacyReceipt>`.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+enum LegacyReceipt<TokenId, BlockNumber> {
+    Tao(Balance, ReceiptStatus<BlockNumber>),
+    Token(TokenId, Balance, ReceiptStatus<BlockNumber>),
+}
+
+/// Dominators used to close without recording since when.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+struct LegacyDominator {
+    pledged: Balance,
+    status: LegacyDominatorStatus,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Encode, Decode, RuntimeDebug)]
+enum LegacyDominatorStatus {
+    Active,
+    Closing,
+    Banned,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Encode, Decode, RuntimeDebug)]
+pub enum Releases {
+    V1,
+    V2,
+}
 
-    fn saturating_pow(self, _: usize) -> Self {
-        unimplemented!();
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1
     }
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum ReceiptStatus<BlockNumber> {
     Active,
     Revoking(BlockNumber),
 }
 
+/// Commitment to the off-chain ledger of a dominator, each one links to its predecessor by `prev`.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct LedgerCommitment<Hash, BlockNumber> {
+    pub root: [u8; 32],
+    pub seq: u64,
+    pub prev: Hash,
+    pub block: BlockNumber,
+}
+
+/// The leaf of a fund owner as the dominator committed it by the last sync of its receipts, along
+/// with the root it was committed in and the TAO value of the receipts at the time.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct CommittedLeaf {
+    pub root: [u8; 32],
+    pub leaf: [u8; 32],
+    pub value: Balance,
+}
+
+/// A pending challenge against the committed leaf of the fund owner, so the receipts moving
+/// afterwards can't make it unanswerable. Unanswered, it costs the dominator `value`.
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
-pub struct Dominator {
+pub struct Challenge<BlockNumber> {
+    pub deadline: BlockNumber,
+    pub root: [u8; 32],
+    pub leaf: [u8; 32],
+    pub value: Balance,
+}
+
+/// The pledge slashed from a dominator and kept for its fund owners, who claim their share pro
+/// rata to `shortfalls`, i.e. `(asset, short, total, value)` with the amount of the asset the
+/// dominator couldn't back of its `total` receipts and its TAO value when the pool was set up.
+/// `owed`, `funds` and `compensation` are what is left to claim of the value short, of the pledge
+/// kept and of what the insurance pot covers.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct SlashPool<TokenId> {
+    pub id: u32,
+    pub shortfalls: Vec<(AssetId<TokenId>, Balance, Balance, Balance)>,
+    pub owed: Balance,
+    pub funds: Balance,
+    pub compensation: Balance,
+}
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct Dominator<BlockNumber> {
     pub pledged: Balance,
-    pub status: DominatorStatus,
+    pub status: DominatorStatus<BlockNumber>,
+}
+
+/// What a dominator tells the fund owners about itself. The caps limit the total hosted value of
+/// each asset, `None` of `tao_cap` means no limit; tokens out of `tokens` are rejected. The fees
+/// are the rates the dominator charges in `settle_trades`, for discovery only.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct DominatorProfile<TokenId> {
+    pub name: Vec<u8>,
+    pub url: Vec<u8>,
+    pub contact: [u8; 32],
+    pub tao_cap: Option<Balance>,
+    pub tokens: Vec<(TokenId, Balance)>,
+    pub maker_fee: Perbill,
+    pub taker_fee: Perbill,
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Encode, Decode, RuntimeDebug)]
-pub enum DominatorStatus {
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum DominatorStatus<BlockNumber> {
     Active,
-    Closing,
+    Closing(BlockNumber),
     Banned,
+    Inactive,
 }
 
 pub trait Trait: frame_system::Trait {
@@ -158,17 +268,184 @@ pub trait Trait: frame_system::Trait {
         + Copy;
 
     type Token: ReservableToken<Self::TokenId, Self::AccountId>;
+
+    type RevokeTimeout: Get<Self::BlockNumber>;
+
+    type ClosingGracePeriod: Get<Self::BlockNumber>;
+
+    type SlashOrigin: EnsureOrigin<Self::Origin>;
+
+    type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+    type MinimumPledge: Get<Balance>;
+
+    type ChallengeWindow: Get<Self::BlockNumber>;
+
+    type ChallengeDeposit: Get<Balance>;
+
+    /// Expired challenges failed in one block, the rest are left to the following blocks.
+    type MaxChallengesPerBlock: Get<u32>;
+
+    /// The most receipts, i.e. `(fund_owner, asset)` pairs, a batch may sync, or trades it may
+    /// settle.
+    type MaxSyncBatch: Get<u32>;
+
+    type MemoExpiry: Get<Self::BlockNumber>;
+
+    type MaxProfileLength: Get<u32>;
+
+    type MaxSupportedTokens: Get<u32>;
+
+    type ProfileDepositBase: Get<Balance>;
+
+    type ProfileDepositPerByte: Get<Balance>;
+
+    type LivenessWindow: Get<Self::BlockNumber>;
+
+    type MaxLivenessChecks: Get<u32>;
+
+    /// The insurance pot is the account of this id.
+    type ModuleId: Get<ModuleId>;
+
+    type InsuranceFeeRate: Get<Perbill>;
+
+    type InsurancePeriod: Get<Self::BlockNumber>;
+
+    type MaxCompensationPerIncident: Get<Balance>;
+
+    /// Prices of the tokens in TAO to value the hosted assets.
+    type Prices: PriceProvider<Self::TokenId>;
+
+    /// The hosted value of a dominator may not exceed `PledgeMultiple` times its pledge.
+    type PledgeMultiple: Get<u32>;
+
+    /// Blocks a dominator has to restore its pledge after a margin call before it is closed.
+    type MarginCallPeriod: Get<Self::BlockNumber>;
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as Receipts {
-        Receipts get(fn receipts): map
-            hasher(blake2_128_concat) HostingPair<T>
-        => Vec<Receipt<T::TokenId, T::BlockNumber>>;
+        HostedReceipts get(fn receipts): double_map
+            hasher(blake2_128_concat) HostingPair<T>,
+            hasher(blake2_128_concat) AssetOf<T>
+        => Option<ReceiptOf<T>>;
 
         Dominators get(fn dominators): map
             hasher(blake2_128_concat) T::AccountId
-        => Option<Dominator>;
+        => Option<DominatorOf<T>>;
+
+        Profiles get(fn profiles): map
+            hasher(blake2_128_concat) T::AccountId
+        => Option<ProfileOf<T>>;
+
+        ProfileDeposits get(fn profile_deposits): map
+            hasher(blake2_128_concat) T::AccountId
+        => Balance;
+
+        LastActive get(fn last_active): map
+            hasher(blake2_128_concat) T::AccountId
+        => Option<T::BlockNumber>;
+
+        LivenessChecks get(fn liveness_checks): map
+            hasher(twox_64_concat) T::BlockNumber
+        => Vec<T::AccountId>;
+
+        InsurancePaidAt get(fn insurance_paid_at): map
+            hasher(blake2_128_concat) T::AccountId
+        => Option<T::BlockNumber>;
+
+        HostedTokens get(fn hosted_tokens): map
+            hasher(blake2_128_concat) T::AccountId
+        => BTreeSet<T::TokenId>;
+
+        MarginCalls get(fn margin_calls): map
+            hasher(blake2_128_concat) T::AccountId
+        => Option<T::BlockNumber>;
+
+        MarginCallDeadlines: map hasher(twox_64_concat) T::BlockNumber => Vec<T::AccountId>;
+
+        Compensations get(fn compensations): double_map
+            hasher(blake2_128_concat) T::AccountId,
+            hasher(blake2_128_concat) T::AccountId
+        => Balance;
+
+        Operators get(fn operators): double_map
+            hasher(blake2_128_concat) T::AccountId,
+            hasher(blake2_128_concat) T::AccountId
+        => bool;
+
+        /// The dominator each operator was added by, which only counts as long as the dominator
+        /// still has it in `Operators`, so closing a dominator leaves these behind.
+        OperatorOf get(fn operator_of): map
+            hasher(blake2_128_concat) T::AccountId
+        => Option<T::AccountId>;
+
+        Ledgers get(fn ledgers): map
+            hasher(blake2_128_concat) T::AccountId
+        => Option<LedgerOf<T>>;
+
+        Hostings get(fn hostings): map
+            hasher(blake2_128_concat) T::AccountId
+        => u32;
+
+        /// The TAO granted to each dominator and not paid out yet, kept apart from whatever else
+        /// the dominator has reserved.
+        HostedTao get(fn hosted_tao): map
+            hasher(blake2_128_concat) T::AccountId
+        => Balance;
+
+        /// The number of receipts each fund owner holds with the dominator, to find the fund
+        /// owners of a dominator without scanning all receipts.
+        FundOwners get(fn fund_owners): double_map
+            hasher(blake2_128_concat) T::AccountId,
+            hasher(blake2_128_concat) T::AccountId
+        => u32;
+
+        /// The total of the receipts of each asset a dominator hosts.
+        ReceiptTotals get(fn receipt_totals): double_map
+            hasher(blake2_128_concat) T::AccountId,
+            hasher(blake2_128_concat) AssetOf<T>
+        => Balance;
+
+        SlashPools get(fn slash_pools): map
+            hasher(blake2_128_concat) T::AccountId
+        => Option<SlashPoolOf<T>>;
+
+        /// The slash pool each fund owner has last claimed its share of.
+        SlashClaims get(fn slash_claims): double_map
+            hasher(blake2_128_concat) T::AccountId,
+            hasher(blake2_128_concat) T::AccountId
+        => Option<u32>;
+
+        CommittedLeaves get(fn committed_leaves): double_map
+            hasher(blake2_128_concat) T::AccountId,
+            hasher(blake2_128_concat) T::AccountId
+        => Option<CommittedLeaf>;
+
+        Challenges get(fn challenges): double_map
+            hasher(blake2_128_concat) T::AccountId,
+            hasher(blake2_128_concat) T::AccountId
+        => Option<ChallengeOf<T>>;
+
+        ChallengeDeadlines get(fn challenge_deadlines): map
+            hasher(twox_64_concat) T::BlockNumber
+        => Vec<(T::AccountId, T::AccountId)>;
+
+        Migrations get(fn migrations): double_map
+            hasher(blake2_128_concat) HostingPair<T>,
+            hasher(blake2_128_concat) AssetOf<T>
+        => Option<T::AccountId>;
+
+        Memos get(fn memos): double_map
+            hasher(blake2_128_concat) HostingPair<T>,
+            hasher(blake2_128_concat) UID
+        => Option<T::BlockNumber>;
+
+        MemoExpirations get(fn memo_expirations): map
+            hasher(twox_64_concat) T::BlockNumber
+        => Vec<(HostingPair<T>, UID)>;
+
+        StorageVersion get(fn storage_version) build(|_| Releases::V2): Releases;
     }
 }
 
@@ -178,15 +455,57 @@ decl_event!(
         AccountId = <T as frame_system::Trait>::AccountId,
         TokenId = <T as Trait>::TokenId,
         Balance = Balance,
+        BlockNumber = <T as frame_system::Trait>::BlockNumber,
+        Trade = TradeOf<T>,
+        Asset = AssetOf<T>,
+        Root = [u8; 32],
     {
         DominatorClaimed(AccountId, Balance),
+        DominatorClosing(AccountId, BlockNumber),
+        DominatorClosed(AccountId, Balance),
+        /// The pledge slashed from a banned dominator and what is left claimable from the
+        /// insurance pot.
+        DominatorSlashed(AccountId, Balance, Balance),
+        /// As `DominatorSlashed`, for the slash that bans the dominator.
+        DominatorBanned(AccountId, Balance, Balance),
+        DominatorInactive(AccountId),
+        DominatorRecovered(AccountId),
+        InsurancePaid(AccountId, Balance),
+        CompensationClaimed(AccountId, AccountId, Balance),
+        MarginCalled(AccountId, BlockNumber),
+        MarginCallCleared(AccountId),
+        PledgeIncreased(AccountId, Balance),
+        PledgeReduced(AccountId, Balance),
+        ProfileUpdated(AccountId, Balance),
+        OperatorAdded(AccountId, AccountId),
+        OperatorRemoved(AccountId, AccountId),
+        /// The changed receipts of the fund owner as `(asset, old, new)`.
+        LedgerSynced(AccountId, AccountId, Vec<(Asset, Balance, Balance)>, u64, Root),
+        /// The changed receipts as `(fund_owner, asset, old, new)`.
+        LedgerBatchSynced(AccountId, Vec<(AccountId, Asset, Balance, Balance)>, u64, Root),
+        TradesSettled(AccountId, Vec<Trade>),
+        Challenged(AccountId, AccountId, BlockNumber),
+        ChallengeAnswered(AccountId, AccountId),
+        ChallengeFailed(AccountId, AccountId),
         TaoHosted(AccountId, AccountId, Balance, UID),
         TokenHosted(AccountId, AccountId, TokenId, Balance, UID),
+        TaoWithdrawRequested(AccountId, AccountId, Balance),
+        TokenWithdrawRequested(AccountId, AccountId, TokenId, Balance),
+        MigrationRequested(AccountId, AccountId, AccountId, Asset),
+        /// The assets freed to the fund owner, and those migrated as `(destination, asset, value)`.
         AssetsClear(
             AccountId,
             AccountId,
             Option<Balance>,
             Vec<(TokenId, Balance)>,
+            Vec<(AccountId, Asset, Balance)>,
+        ),
+        AssetsForceWithdrawn(
+            AccountId,
+            AccountId,
+            Option<Balance>,
+            Vec<(TokenId, Balance)>,
+            Vec<(AccountId, Asset, Balance)>,
         ),
     }
 );
@@ -205,6 +524,26 @@ decl_error! {
         InsufficientBalance,
         InsufficientStashAccount,
         InvalidStatus,
+        RevokingNotExpired,
+        LedgerNotExists,
+        ChallengeAlreadyExists,
+        ChallengeNotExists,
+        InvalidProof,
+        BatchTooLarge,
+        UnbalancedSync,
+        InsufficientReceipt,
+        DuplicatedMemo,
+        OperatorAlreadyExists,
+        OperatorNotFound,
+        ProfileTooLarge,
+        TokenNotSupported,
+        HostingCapExceeded,
+        NoCompensation,
+        InsufficientInsurance,
+        PriceNotAvailable,
+        CollateralInsufficient,
+        CollateralSufficient,
+        ReceiptsOutstanding,
     }
 }
 
@@ -214,20 +553,183 @@ decl_module! {
 
         fn deposit_event() = default;
 
+        const RevokeTimeout: T::BlockNumber = T::RevokeTimeout::get();
+
+        const ClosingGracePeriod: T::BlockNumber = T::ClosingGracePeriod::get();
+
+        const MinimumPledge: Balance = T::MinimumPledge::get();
+
+        const ChallengeWindow: T::BlockNumber = T::ChallengeWindow::get();
+
+        const ChallengeDeposit: Balance = T::ChallengeDeposit::get();
+
+        const MaxChallengesPerBlock: u32 = T::MaxChallengesPerBlock::get();
+
+        const MaxSyncBatch: u32 = T::MaxSyncBatch::get();
+
+        const MemoExpiry: T::BlockNumber = T::MemoExpiry::get();
+
+        const MaxProfileLength: u32 = T::MaxProfileLength::get();
+
+        const MaxSupportedTokens: u32 = T::MaxSupportedTokens::get();
+
+        const ProfileDepositBase: Balance = T::ProfileDepositBase::get();
+
+        const ProfileDepositPerByte: Balance = T::ProfileDepositPerByte::get();
+
+        const LivenessWindow: T::BlockNumber = T::LivenessWindow::get();
+
+        const MaxLivenessChecks: u32 = T::MaxLivenessChecks::get();
+
+        const ModuleId: ModuleId = T::ModuleId::get();
+
+        const InsuranceFeeRate: Perbill = T::InsuranceFeeRate::get();
+
+        const InsurancePeriod: T::BlockNumber = T::InsurancePeriod::get();
+
+        const MaxCompensationPerIncident: Balance = T::MaxCompensationPerIncident::get();
+
+        const PledgeMultiple: u32 = T::PledgeMultiple::get();
+
+        const MarginCallPeriod: T::BlockNumber = T::MarginCallPeriod::get();
+
+        fn on_runtime_upgrade() -> Weight {
+            let mut weight: Weight = 0;
+            if Self::storage_version() == Releases::V1 {
+                weight = weight.saturating_add(Self::migrate_to_v2());
+            }
+            weight
+        }
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            let mut expired = ChallengeDeadlines::<T>::take(now);
+            let max = T::MaxChallengesPerBlock::get() as usize;
+            if expired.len() > max {
+                let rest = expired.split_off(max);
+                ChallengeDeadlines::<T>::mutate(now.saturating_add(One::one()), |next| next.extend(rest));
+            }
+            let mut weight = (expired.len() as Weight).saturating_mul(10_000_000);
+            for (dominator, fund_owner) in expired {
+                Self::fail_challenge(&dominator, &fund_owner, now);
+            }
+            let memos = MemoExpirations::<T>::take(now);
+            weight = weight.saturating_add(T::DbWeight::get().writes(memos.len() as Weight));
+            for (pair, memo) in memos {
+                Memos::<T>::remove(pair, memo);
+            }
+            let mut due = LivenessChecks::<T>::take(now);
+            let max = T::MaxLivenessChecks::get() as usize;
+            if due.len() > max {
+                // leave the rest to the next block
+                let rest = due.split_off(max);
+                LivenessChecks::<T>::mutate(now.saturating_add(One::one()), |next| next.extend(rest));
+            }
+            // each check may collect the insurance fee and review the margin as well
+            weight = weight.saturating_add((due.len() as Weight).saturating_mul(10_000_000));
+            for dominator in due {
+                Self::check_liveness(&dominator, now);
+            }
+            let called = MarginCallDeadlines::<T>::take(now);
+            weight = weight.saturating_add((called.len() as Weight).saturating_mul(10_000_000));
+            for dominator in called {
+                Self::close_margin_call(&dominator, now);
+            }
+            weight
+        }
+
         #[weight = 1_000_000]
         pub fn claim_dominator(origin, #[compact] pledge: Balance) {
             let dominator = ensure_signed(origin)?;
             ensure!(!<Dominators<T>>::contains_key(&dominator), Error::<T>::DominatorAlreadyExists);
+            ensure!(Self::operating_for(&dominator).is_none(), Error::<T>::OperatorAlreadyExists);
+            ensure!(pledge >= T::MinimumPledge::get(), Error::<T>::PledgeUnsatisfied);
             let v: BalanceOf<T> = pledge.try_into().or(Err(Error::<T>::IllegalParameters))?;
             // TODO lock
             T::Currency::reserve(&dominator, v)?;
-            <Dominators<T>>::insert(&dominator, Dominator {
+            let claimed = Dominator {
                 pledged: pledge,
                 status: DominatorStatus::Active,
-            });
+            };
+            <Dominators<T>>::insert(&dominator, &claimed);
+            Self::touch(&dominator, &claimed);
             Self::deposit_event(RawEvent::DominatorClaimed(dominator, pledge));
         }
 
+        #[weight = 1_000_000]
+        pub fn add_pledge(origin, #[compact] amount: Balance) {
+            let dominator = ensure_signed(origin)?;
+            let mut claimed = Dominators::<T>::get(&dominator).ok_or(Error::<T>::DominatorNotFound)?;
+            ensure!(claimed.status == DominatorStatus::Active, Error::<T>::InvalidStatus);
+            let v: BalanceOf<T> = amount.try_into().or(Err(Error::<T>::IllegalParameters))?;
+            claimed.pledged = claimed.pledged.checked_add(amount).ok_or(Error::<T>::IllegalParameters)?;
+            T::Currency::reserve(&dominator, v)?;
+            Dominators::<T>::insert(&dominator, &claimed);
+            Self::deposit_event(RawEvent::PledgeIncreased(dominator, amount));
+        }
+
+        #[weight = 1_000_000]
+        pub fn reduce_pledge(origin, #[compact] amount: Balance) {
+            let dominator = ensure_signed(origin)?;
+            let mut claimed = Dominators::<T>::get(&dominator).ok_or(Error::<T>::DominatorNotFound)?;
+            ensure!(claimed.status == DominatorStatus::Active, Error::<T>::InvalidStatus);
+            let v: BalanceOf<T> = amount.try_into().or(Err(Error::<T>::IllegalParameters))?;
+            let pledged = claimed.pledged.checked_sub(amount).ok_or(Error::<T>::PledgeUnsatisfied)?;
+            ensure!(pledged >= T::MinimumPledge::get(), Error::<T>::PledgeUnsatisfied);
+            Self::ensure_collateralized(&dominator, pledged, 0)?;
+            claimed.pledged = pledged;
+            T::Currency::unreserve(&dominator, v);
+            Dominators::<T>::insert(&dominator, &claimed);
+            Self::deposit_event(RawEvent::PledgeReduced(dominator, amount));
+        }
+
+        #[weight = 1_000_000]
+        pub fn set_profile(origin, profile: ProfileOf<T>) {
+            let dominator = ensure_signed(origin)?;
+            let claimed = Dominators::<T>::get(&dominator).ok_or(Error::<T>::DominatorNotFound)?;
+            ensure!(claimed.status == DominatorStatus::Active, Error::<T>::InvalidStatus);
+            let max = T::MaxProfileLength::get() as usize;
+            ensure!(profile.name.len() <= max && profile.url.len() <= max, Error::<T>::ProfileTooLarge);
+            ensure!(profile.tokens.len() as u32 <= T::MaxSupportedTokens::get(), Error::<T>::ProfileTooLarge);
+            let tokens = profile.tokens.iter().map(|(id, _)| id).collect::<BTreeSet<_>>();
+            ensure!(tokens.len() == profile.tokens.len(), Error::<T>::IllegalParameters);
+            let deposit = T::ProfileDepositPerByte::get()
+                .saturating_mul(profile.encode().len() as Balance)
+                .saturating_add(T::ProfileDepositBase::get());
+            let old = ProfileDeposits::<T>::get(&dominator);
+            if deposit > old {
+                let more: BalanceOf<T> = (deposit - old).try_into().or(Err(Error::<T>::IllegalParameters))?;
+                T::Currency::reserve(&dominator, more).map_err(|_| Error::<T>::InsufficientBalance)?;
+            } else {
+                let less: BalanceOf<T> = (old - deposit).try_into().or(Err(Error::<T>::IllegalParameters))?;
+                T::Currency::unreserve(&dominator, less);
+            }
+            Profiles::<T>::insert(&dominator, profile);
+            ProfileDeposits::<T>::insert(&dominator, deposit);
+            Self::deposit_event(RawEvent::ProfileUpdated(dominator, deposit));
+        }
+
+        #[weight = 1_000_000]
+        pub fn add_operator(origin, operator: <T::Lookup as StaticLookup>::Source) {
+            let dominator = ensure_signed(origin)?;
+            let operator = T::Lookup::lookup(operator)?;
+            ensure!(Dominators::<T>::contains_key(&dominator), Error::<T>::DominatorNotFound);
+            ensure!(!Dominators::<T>::contains_key(&operator), Error::<T>::DominatorAlreadyExists);
+            ensure!(Self::operating_for(&operator).is_none(), Error::<T>::OperatorAlreadyExists);
+            Operators::<T>::insert(&dominator, &operator, true);
+            OperatorOf::<T>::insert(&operator, &dominator);
+            Self::deposit_event(RawEvent::OperatorAdded(dominator, operator));
+        }
+
+        #[weight = 1_000_000]
+        pub fn remove_operator(origin, operator: <T::Lookup as StaticLookup>::Source) {
+            let dominator = ensure_signed(origin)?;
+            let operator = T::Lookup::lookup(operator)?;
+            ensure!(Operators::<T>::get(&dominator, &operator), Error::<T>::OperatorNotFound);
+            Operators::<T>::remove(&dominator, &operator);
+            OperatorOf::<T>::remove(&operator);
+            Self::deposit_event(RawEvent::OperatorRemoved(dominator, operator));
+        }
+
         #[weight = 100_000]
         pub fn grant_tao(origin,
                          dominator: <T::Lookup as StaticLookup>::Source,
@@ -239,19 +741,18 @@ decl_module! {
             ensure!(claimed.status == DominatorStatus::Active, Error::<T>::InvalidStatus);
             let value: BalanceOf<T> = amount.try_into().or(Err(Error::<T>::IllegalParameters))?;
             ensure!(T::Currency::can_reserve(&fund_owner, value), Error::<T>::InsufficientBalance);
-            Self::try_mutate_receipt(&fund_owner,
-                                     &dominator,
-                                     |r| matches!(r, Receipt::Tao(_, _)),
-                                     |exists| {
-                                         let taken = exists.take().unwrap_or(Receipt::Tao(Zero::zero(), ReceiptStatus::Active));
-                                         if !taken.is_active() {
-                                             return Err(Error::<T>::InvalidStatus);
-                                         }
-                                         exists.replace(Receipt::Tao(taken.get_value().saturating_add(amount), ReceiptStatus::Active));
-                                         Ok(())
-                                     })?;
+            Self::ensure_hostable(&dominator, &claimed, &AssetId::Tao, amount)?;
+            ensure!(!Memos::<T>::contains_key((&fund_owner, &dominator), memo), Error::<T>::DuplicatedMemo);
+            Self::try_mutate_receipt(&fund_owner, &dominator, &AssetId::Tao, |exists| {
+                let taken = exists.take().unwrap_or_else(|| Receipt::new(Zero::zero()));
+                ensure!(taken.is_active(), Error::<T>::InvalidStatus);
+                exists.replace(Receipt::new(taken.value.saturating_add(amount)));
+                Ok(())
+            })?;
             T::Currency::reserve(&fund_owner, value)?;
             T::Currency::repatriate_reserved(&fund_owner, &dominator, value, BalanceStatus::Reserved)?;
+            HostedTao::<T>::mutate(&dominator, |hosted| *hosted = hosted.saturating_add(amount));
+            Self::record_memo(&fund_owner, &dominator, memo);
             Self::deposit_event(RawEvent::TaoHosted(fund_owner, dominator, amount, memo));
         }
 
@@ -267,19 +768,17 @@ decl_module! {
             ensure!(claimed.status == DominatorStatus::Active, Error::<T>::InvalidStatus);
             let value: TokenBalanceOf<T> = amount.try_into().or(Err(Error::<T>::IllegalParameters))?;
             ensure!(T::Token::can_reserve(&token, &fund_owner, value), Error::<T>::InsufficientBalance);
-            Self::try_mutate_receipt(&fund_owner,
-                                     &dominator,
-                                     |r| r.is_token_of(&token),
-                                     |exists| {
-                                         let taken = exists.take().unwrap_or(Receipt::Token(token, Zero::zero(), ReceiptStatus::Active));
-                                         if !taken.is_active() {
-                                             return Err(Error::<T>::InvalidStatus);
-                                         }
-                                         exists.replace(Receipt::Tao(taken.get_value().saturating_add(amount), ReceiptStatus::Active));
-                                         Ok(())
-                                     })?;
+            Self::ensure_hostable(&dominator, &claimed, &AssetId::Token(token), amount)?;
+            ensure!(!Memos::<T>::contains_key((&fund_owner, &dominator), memo), Error::<T>::DuplicatedMemo);
+            Self::try_mutate_receipt(&fund_owner, &dominator, &AssetId::Token(token), |exists| {
+                let taken = exists.take().unwrap_or_else(|| Receipt::new(Zero::zero()));
+                ensure!(taken.is_active(), Error::<T>::InvalidStatus);
+                exists.replace(Receipt::new(taken.value.saturating_add(amount)));
+                Ok(())
+            })?;
             T::Token::reserve(&token, &fund_owner, value)?;
             T::Token::repatriate_reserved(&token, &fund_owner, &dominator, value, BalanceStatus::Reserved)?;
+            Self::record_memo(&fund_owner, &dominator, memo);
             Self::deposit_event(RawEvent::TokenHosted(fund_owner, dominator, token, amount, memo));
         }
 
@@ -288,40 +787,62 @@ decl_module! {
                     fund_owner: <T::Lookup as StaticLookup>::Source,
                     tao: Balance,
                     tokens: Vec<(T::TokenId, Balance)>,
-                    digest: Vec<u8>) {
-            let dominator = ensure_signed(origin)?;
+                    root: [u8; 32]) {
+            let dominator = Self::ensure_operator(ensure_signed(origin)?)?;
             let fund_owner = T::Lookup::lookup(fund_owner)?;
-            ensure!(Dominators::<T>::contains_key(&dominator), Error::<T>::DominatorNotFound);
-            Self::try_mutate_receipt(&fund_owner,
-                                     &dominator,
-                                     |r| matches!(r, Receipt::Tao(_, _)),
-                                     |exists_tao| {
-                                         match exists_tao.take() {
-                                             None => {},
-                                             Some(Receipt::Tao(0, _)) => {},
-                                             Some(Receipt::Token(_,_,_)) => {}
-                                             Some(Receipt::Tao(_, s)) => {
-                                                 exists_tao.replace(Receipt::Tao(tao, s));
-                                             }
-                                         }
-                                         Ok(())
-                                     })?;
-            for (token, value) in tokens {
-                Self::try_mutate_receipt(&fund_owner,
-                                         &dominator,
-                                         |r| r.is_token_of(&token),
-                                         |exists_token| {
-                                             match exists_token.take() {
-                                                 None => {}
-                                                 Some(Receipt::Token(_, 0, _)) => {}
-                                                 Some(Receipt::Tao(_, _)) => {}
-                                                 Some(Receipt::Token(t, _, s)) => {
-                                                     exists_token.replace(Receipt::Token(t, value, s));
-                                                 }
-                                             }
-                                             Ok(())
-                                         })?;
+            let claimed = Dominators::<T>::get(&dominator).ok_or(Error::<T>::DominatorNotFound)?;
+            ensure!(claimed.status != DominatorStatus::Banned, Error::<T>::DominatorBanned);
+            let changes = sp_std::iter::once((fund_owner.clone(), AssetId::Tao, tao))
+                .chain(tokens.into_iter().map(|(id, v)| (fund_owner.clone(), AssetId::Token(id), v)))
+                .collect::<Vec<_>>();
+            Self::ensure_conserved(&dominator, &changes)?;
+            let mut synced = vec![];
+            for (_, asset, value) in changes {
+                if let Some((old, new)) = Self::sync_receipt(&fund_owner, &dominator, &asset, value) {
+                    synced.push((asset, old, new));
+                }
             }
+            Self::touch(&dominator, &claimed);
+            let ledger = Self::commit(&dominator, root);
+            Self::commit_leaf(&dominator, &fund_owner, root);
+            Self::deposit_event(RawEvent::LedgerSynced(dominator, fund_owner, synced, ledger.seq, root));
+        }
+
+        #[weight = (Module::<T>::batch_size(entries) as Weight).saturating_add(1).saturating_mul(100_000)]
+        pub fn sync_batch(origin,
+                          entries: Vec<LedgerEntryOf<T>>,
+                          root: [u8; 32]) {
+            let dominator = Self::ensure_operator(ensure_signed(origin)?)?;
+            ensure!(Self::batch_size(&entries) <= T::MaxSyncBatch::get() as usize, Error::<T>::BatchTooLarge);
+            let claimed = Dominators::<T>::get(&dominator).ok_or(Error::<T>::DominatorNotFound)?;
+            ensure!(claimed.status != DominatorStatus::Banned, Error::<T>::DominatorBanned);
+            let mut changes = vec![];
+            for (fund_owner, tao, tokens) in entries {
+                changes.push((fund_owner.clone(), AssetId::Tao, tao));
+                for (token, value) in tokens {
+                    changes.push((fund_owner.clone(), AssetId::Token(token), value));
+                }
+            }
+            Self::ensure_conserved(&dominator, &changes)?;
+            let owners = changes.iter().map(|(owner, _, _)| owner.clone()).collect::<BTreeSet<_>>();
+            let mut synced = vec![];
+            for (fund_owner, asset, value) in changes {
+                if let Some((old, new)) = Self::sync_receipt(&fund_owner, &dominator, &asset, value) {
+                    synced.push((fund_owner, asset, old, new));
+                }
+            }
+            Self::touch(&dominator, &claimed);
+            let ledger = Self::commit(&dominator, root);
+            for owner in owners {
+                Self::commit_leaf(&dominator, &owner, root);
+            }
+            Self::deposit_event(RawEvent::LedgerBatchSynced(dominator, synced, ledger.seq, root));
+        }
+
+        #[weight = (trades.len() as Weight).saturating_add(1).saturating_mul(200_000)]
+        #[transactional]
+        pub fn settle_trades(origin, trades: Vec<TradeOf<T>>) -> DispatchResult {
+            Self::do_settle_trades(origin, trades)
         }
 
         #[weight = 100_000]
@@ -330,20 +851,8 @@ decl_module! {
             let dominator = T::Lookup::lookup(dominator)?;
             ensure!(Dominators::<T>::contains_key(&dominator), Error::<T>::DominatorNotFound);
             let t = frame_system::Module::<T>::block_number();
-            Self::try_mutate_receipt_exists(&fund_owner,
-                                            &dominator,
-                                            |r| matches!(r, Receipt::Tao(_, ReceiptStatus::Active)),
-                                            |exists| {
-                                                match exists.take() {
-                                                    None => {}
-                                                    Some(Receipt::Tao(0, _)) => {}
-                                                    Some(Receipt::Token(_, _, _)) => {}
-                                                    Some(Receipt::Tao(v, _)) => {
-                                                        exists.replace(Receipt::Tao(v, ReceiptStatus::Revoking(t)));
-                                                    }
-                                                }
-                                                Ok(())
-                                            })?;
+            let value = Self::revoke_receipt(&fund_owner, &dominator, &AssetId::Tao, t)?;
+            Self::deposit_event(RawEvent::TaoWithdrawRequested(fund_owner, dominator, value));
         }
 
         #[weight = 100_000]
@@ -352,87 +861,1244 @@ decl_module! {
             let dominator = T::Lookup::lookup(dominator)?;
             ensure!(Dominators::<T>::contains_key(&dominator), Error::<T>::DominatorNotFound);
             let t = frame_system::Module::<T>::block_number();
-            Self::try_mutate_receipt_exists(&fund_owner,
-                                            &dominator,
-                                            |r| r.is_active() && r.is_token_of(&token_id),
-                                            |exists| {
-                                                match exists.take() {
-                                                    None => {}
-                                                    Some(Receipt::Token(_, 0, _)) => {}
-                                                    Some(Receipt::Tao(_, _)) => {}
-                                                    Some(Receipt::Token(id, v, _)) => {
-                                                        exists.replace(Receipt::Token(id, v, ReceiptStatus::Revoking(t)));
-                                                    }
-                                                }
-                                                Ok(())
-                                            })?;
-        }
-
-        #[weight = 1000]
-        pub fn confirm(origin, fund_owner: <T::Lookup as StaticLookup>::Source) {
-            let dominator = ensure_signed(origin)?;
+            let value = Self::revoke_receipt(&fund_owner, &dominator, &AssetId::Token(token_id), t)?;
+            Self::deposit_event(RawEvent::TokenWithdrawRequested(fund_owner, dominator, token_id, value));
+        }
+
+        #[weight = 100_000]
+        pub fn migrate_receipt(origin,
+                               from: <T::Lookup as StaticLookup>::Source,
+                               to: <T::Lookup as StaticLookup>::Source,
+                               asset: AssetOf<T>) {
+            let fund_owner = ensure_signed(origin)?;
+            let from = T::Lookup::lookup(from)?;
+            let to = T::Lookup::lookup(to)?;
+            ensure!(from != to, Error::<T>::IllegalParameters);
+            ensure!(Dominators::<T>::contains_key(&from), Error::<T>::DominatorNotFound);
+            let dest = Dominators::<T>::get(&to).ok_or(Error::<T>::DominatorNotFound)?;
+            ensure!(dest.status == DominatorStatus::Active, Error::<T>::InvalidStatus);
+            let value = HostedReceipts::<T>::get((&fund_owner, &from), &asset)
+                .map(|r| r.value)
+                .ok_or(Error::<T>::ReceiptNotExists)?;
+            // nothing to migrate, the empty receipt is to be withdrawn instead
+            ensure!(value > 0, Error::<T>::InsufficientReceipt);
+            Self::ensure_hostable(&to, &dest, &asset, value)?;
+            ensure!(
+                !HostedReceipts::<T>::get((&fund_owner, &to), &asset).map(|r| r.is_revoking()).unwrap_or(false),
+                Error::<T>::InvalidStatus
+            );
+            let t = frame_system::Module::<T>::block_number();
+            Self::revoke_receipt(&fund_owner, &from, &asset, t)?;
+            Migrations::<T>::insert((&fund_owner, &from), &asset, &to);
+            Self::deposit_event(RawEvent::MigrationRequested(fund_owner, from, to, asset));
+        }
 
+        #[weight = 1_000_000]
+        #[transactional]
+        pub fn confirm(origin, fund_owner: <T::Lookup as StaticLookup>::Source) -> DispatchResult {
+            Self::do_confirm(origin, fund_owner)
         }
 
-        #[weight = 1000]
+        #[weight = 1_000_000]
+        #[transactional]
+        pub fn force_withdraw(origin, dominator: <T::Lookup as StaticLookup>::Source) -> DispatchResult {
+            Self::do_force_withdraw(origin, dominator)
+        }
+
+        #[weight = 1_000_000]
         pub fn quit_dominator(origin) {
             let dominator = ensure_signed(origin)?;
+            let mut claimed = Dominators::<T>::get(&dominator).ok_or(Error::<T>::DominatorNotFound)?;
+            ensure!(
+                matches!(claimed.status, DominatorStatus::Active | DominatorStatus::Inactive),
+                Error::<T>::InvalidStatus
+            );
+            let now = frame_system::Module::<T>::block_number();
+            claimed.status = DominatorStatus::Closing(now);
+            Dominators::<T>::insert(&dominator, &claimed);
+            // closed right away if nothing is hosted
+            if !Self::try_close(&dominator, &claimed) {
+                Self::deposit_event(RawEvent::DominatorClosing(dominator, now));
+            }
         }
 
-        #[weight = 1000]
-        pub fn slash(origin, dominator: <T::Lookup as StaticLookup>::Source) {
-            // ensure_root(origin)?;
+        /// Anyone may release the pledge of a closing dominator once its last receipt is gone,
+        /// however it went, e.g. confirmed or force withdrawn.
+        #[weight = 1_000_000]
+        pub fn close_dominator(origin, dominator: <T::Lookup as StaticLookup>::Source) {
+            ensure_signed(origin)?;
+            let dominator = T::Lookup::lookup(dominator)?;
+            let claimed = Dominators::<T>::get(&dominator).ok_or(Error::<T>::DominatorNotFound)?;
+            ensure!(matches!(claimed.status, DominatorStatus::Closing(_)), Error::<T>::InvalidStatus);
+            ensure!(Hostings::<T>::get(&dominator) == 0, Error::<T>::ReceiptsOutstanding);
+            Self::try_close(&dominator, &claimed);
+        }
+
+        #[weight = 10_000_000]
+        pub fn slash(origin, dominator: <T::Lookup as StaticLookup>::Source, #[compact] amount: Balance) {
+            T::SlashOrigin::ensure_origin(origin)?;
+            let dominator = T::Lookup::lookup(dominator)?;
+            Self::do_slash(&dominator, amount)?;
+        }
+
+        /// Anyone may call the margin of a dominator whose hosted value exceeds its pledge
+        /// multiple, it is closed unless the pledge is restored within `MarginCallPeriod`.
+        #[weight = 1_000_000]
+        pub fn margin_call(origin, dominator: <T::Lookup as StaticLookup>::Source) {
+            ensure_signed(origin)?;
+            let dominator = T::Lookup::lookup(dominator)?;
+            let claimed = Dominators::<T>::get(&dominator).ok_or(Error::<T>::DominatorNotFound)?;
+            ensure!(claimed.status == DominatorStatus::Active, Error::<T>::InvalidStatus);
+            ensure!(!MarginCalls::<T>::contains_key(&dominator), Error::<T>::InvalidStatus);
+            match Self::ensure_collateralized(&dominator, claimed.pledged, 0) {
+                Err(Error::<T>::CollateralInsufficient) => Self::check_margin(&dominator, &claimed),
+                Err(e) => return Err(e.into()),
+                Ok(()) => return Err(Error::<T>::CollateralSufficient.into()),
+            }
+        }
+
+        /// Claim the share of the slashed pledge kept for the fund owner, and what is owed to it
+        /// from the insurance pot as far as the pot goes.
+        #[weight = 1_000_000]
+        #[transactional]
+        pub fn claim_compensation(origin, dominator: <T::Lookup as StaticLookup>::Source) -> DispatchResult {
+            Self::do_claim_compensation(origin, dominator)
+        }
+
+        #[weight = 1_000_000]
+        pub fn challenge(origin, dominator: <T::Lookup as StaticLookup>::Source) {
+            let fund_owner = ensure_signed(origin)?;
+            let dominator = T::Lookup::lookup(dominator)?;
+            let claimed = Dominators::<T>::get(&dominator).ok_or(Error::<T>::DominatorNotFound)?;
+            ensure!(claimed.status != DominatorStatus::Banned, Error::<T>::DominatorBanned);
+            ensure!(HostedReceipts::<T>::iter_prefix((&fund_owner, &dominator)).next().is_some(), Error::<T>::ReceiptNotExists);
+            // only what the dominator has committed can be challenged
+            let committed = CommittedLeaves::<T>::get(&dominator, &fund_owner).ok_or(Error::<T>::LedgerNotExists)?;
+            ensure!(!Challenges::<T>::contains_key(&dominator, &fund_owner), Error::<T>::ChallengeAlreadyExists);
+            let deposit: BalanceOf<T> = T::ChallengeDeposit::get()
+                .try_into()
+                .or(Err(Error::<T>::IllegalParameters))?;
+            T::Currency::reserve(&fund_owner, deposit)?;
+            let deadline = frame_system::Module::<T>::block_number().saturating_add(T::ChallengeWindow::get());
+            Challenges::<T>::insert(&dominator, &fund_owner, Challenge {
+                deadline,
+                root: committed.root,
+                leaf: committed.leaf,
+                value: committed.value,
+            });
+            ChallengeDeadlines::<T>::mutate(deadline, |pending| pending.push((dominator.clone(), fund_owner.clone())));
+            Self::deposit_event(RawEvent::Challenged(dominator, fund_owner, deadline));
+        }
+
+        #[weight = 1_000_000]
+        pub fn answer(origin,
+                      fund_owner: <T::Lookup as StaticLookup>::Source,
+                      proof: Vec<[u8; 32]>) {
+            let dominator = Self::ensure_operator(ensure_signed(origin)?)?;
+            let fund_owner = T::Lookup::lookup(fund_owner)?;
+            let challenge = Challenges::<T>::get(&dominator, &fund_owner).ok_or(Error::<T>::ChallengeNotExists)?;
+            ensure!(verify_proof(&challenge.root, challenge.leaf, &proof), Error::<T>::InvalidProof);
+            Challenges::<T>::remove(&dominator, &fund_owner);
+            ChallengeDeadlines::<T>::mutate(challenge.deadline, |pending| pending.retain(|(d, o)| d != &dominator || o != &fund_owner));
+            // the deposit goes to the dominator for its trouble
+            let deposit: BalanceOf<T> = T::ChallengeDeposit::get().try_into().unwrap_or_else(|_| Zero::zero());
+            T::Currency::repatriate_reserved(&fund_owner, &dominator, deposit, BalanceStatus::Free)?;
+            Self::deposit_event(RawEvent::ChallengeAnswered(dominator, fund_owner));
         }
     }
 }
 
-type ReceiptOf<T> = Receipt<<T as Trait>::TokenId, <T as frame_system::Trait>::BlockNumber>;
+/// Verify the `leaf` against the `root` by hashing along the `proof`, each pair of nodes is
+/// sorted before being hashed so the proof doesn't need to carry the positions.
+pub fn verify_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        let mut pair = [0u8; 64];
+        if node <= *sibling {
+            pair[..32].copy_from_slice(&node);
+            pair[32..].copy_from_slice(sibling);
+        } else {
+            pair[..32].copy_from_slice(sibling);
+            pair[32..].copy_from_slice(&node);
+        }
+        sp_io::hashing::blake2_256(&pair)
+    });
+    computed == *root
+}
+
+pub type ProfileOf<T> = DominatorProfile<<T as Trait>::TokenId>;
+
+pub type TradeOf<T> = Trade<<T as frame_system::Trait>::AccountId, <T as Trait>::TokenId>;
+
+pub type AssetOf<T> = AssetId<<T as Trait>::TokenId>;
+
+pub type ReceiptOf<T> = Receipt<<T as frame_system::Trait>::BlockNumber>;
+
+pub type DominatorOf<T> = Dominator<<T as frame_system::Trait>::BlockNumber>;
+
+pub type ChallengeOf<T> = Challenge<<T as frame_system::Trait>::BlockNumber>;
+
+pub type SlashPoolOf<T> = SlashPool<<T as Trait>::TokenId>;
+
+/// A fund owner with its TAO and tokens, as the dominator keeps them in its ledger.
+pub type LedgerEntry<AccountId, TokenId> = (AccountId, Balance, Vec<(TokenId, Balance)>);
+
+pub type LedgerEntryOf<T> =
+    LedgerEntry<<T as frame_system::Trait>::AccountId, <T as Trait>::TokenId>;
+
+type LedgerOf<T> = LedgerCommitment<
+    <T as frame_system::Trait>::Hash,
+    <T as frame_system::Trait>::BlockNumber,
+>;
+
+/// Slashed pledges feed the insurance pot when the module itself is used as `T::Slash`.
+impl<T: Trait> OnUnbalanced<NegativeImbalanceOf<T>> for Module<T> {
+    fn on_nonzero_unbalanced(amount: NegativeImbalanceOf<T>) {
+        T::Currency::resolve_creating(&Self::account_id(), amount);
+    }
+}
 
 impl<T: Trait> Module<T> {
-    fn try_mutate_receipt(
-        owner: &T::AccountId,
+    /// All receipts of the fund owner, along with the dominators hosting them.
+    pub fn receipts_of(fund_owner: &T::AccountId) -> Vec<(T::AccountId, AssetOf<T>, ReceiptOf<T>)> {
+        Dominators::<T>::iter()
+            .flat_map(|(dominator, _)| {
+                HostedReceipts::<T>::iter_prefix((fund_owner, &dominator))
+                    .map(move |(asset, r)| (dominator.clone(), asset, r))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// The TAO and tokens hosted by the dominator, grouped by fund owner.
+    pub fn hosted_by(
         dominator: &T::AccountId,
-        filter: impl Fn(&ReceiptOf<T>) -> bool,
-        mutator: impl FnOnce(&mut Option<ReceiptOf<T>>) -> Result<(), Error<T>>,
+    ) -> Vec<LedgerEntryOf<T>> {
+        FundOwners::<T>::iter_prefix(dominator)
+            .map(|(owner, _)| {
+                let mut tao = 0;
+                let mut tokens = vec![];
+                for (asset, r) in HostedReceipts::<T>::iter_prefix((&owner, dominator)) {
+                    match asset {
+                        AssetId::Tao => tao = r.value,
+                        AssetId::Token(id) => tokens.push((id, r.value)),
+                    }
+                }
+                (owner, tao, tokens)
+            })
+            .collect()
+    }
+
+    /// The number of receipts a batch syncs, the TAO of each entry along with its tokens.
+    fn batch_size(entries: &[LedgerEntryOf<T>]) -> usize {
+        entries
+            .iter()
+            .fold(0usize, |n, (_, _, tokens)| n.saturating_add(tokens.len()).saturating_add(1))
+    }
+
+    fn do_settle_trades(origin: T::Origin, trades: Vec<TradeOf<T>>) -> DispatchResult {
+        let dominator = Self::ensure_operator(ensure_signed(origin)?)?;
+        ensure!(trades.len() as u32 <= T::MaxSyncBatch::get(), Error::<T>::BatchTooLarge);
+        let claimed = Dominators::<T>::get(&dominator).ok_or(Error::<T>::DominatorNotFound)?;
+        ensure!(claimed.status != DominatorStatus::Banned, Error::<T>::DominatorBanned);
+        for trade in trades.iter() {
+            ensure!(trade.maker != trade.taker, Error::<T>::IllegalParameters);
+            ensure!(trade.base != trade.quote, Error::<T>::IllegalParameters);
+            let turnover = multiply_by_rational(trade.quantity, trade.price, PRICE_UNIT)
+                .map_err(|_| Error::<T>::IllegalParameters)?;
+            let (paid, received) = match trade.side {
+                Side::Buy => ((trade.quote, turnover), (trade.base, trade.quantity)),
+                Side::Sell => ((trade.base, trade.quantity), (trade.quote, turnover)),
+            };
+            ensure!(trade.fee <= received.1, Error::<T>::IllegalParameters);
+            Self::transfer_receipt(&dominator, &trade.taker, &trade.maker, &paid.0, paid.1)?;
+            Self::transfer_receipt(&dominator, &trade.maker, &trade.taker, &received.0, received.1)?;
+            Self::charge_fee(&dominator, &trade.taker, &received.0, trade.fee)?;
+        }
+        Self::touch(&dominator, &claimed);
+        Self::deposit_event(RawEvent::TradesSettled(dominator, trades));
+        Ok(())
+    }
+
+    fn do_confirm(origin: T::Origin, fund_owner: <T::Lookup as StaticLookup>::Source) -> DispatchResult {
+        let dominator = Self::ensure_operator(ensure_signed(origin)?)?;
+        let fund_owner = T::Lookup::lookup(fund_owner)?;
+        // the receipts take their share of a slash first, so what is left of them is backed
+        Self::settle_slash(&dominator, &fund_owner)?;
+        let claimed = Dominators::<T>::get(&dominator).ok_or(Error::<T>::DominatorNotFound)?;
+        let revoking = HostedReceipts::<T>::iter_prefix((&fund_owner, &dominator))
+            .filter(|(_, r)| r.is_revoking())
+            .collect::<Vec<_>>();
+        ensure!(!revoking.is_empty(), Error::<T>::ReceiptNotExists);
+        // check all before moving anything
+        for (asset, r) in revoking.iter() {
+            Self::ensure_repatriable(&dominator, &claimed, asset, r.value)?;
+        }
+        let mut tao = None;
+        let mut tokens = vec![];
+        let mut migrated = vec![];
+        for (asset, r) in revoking {
+            // a migrating receipt goes to the reserved balance of the destination instead
+            let target = Self::migration_target(&fund_owner, &dominator, &asset, r.value);
+            let (to, status) = match target.as_ref() {
+                Some(dest) => (dest, BalanceStatus::Reserved),
+                None => (&fund_owner, BalanceStatus::Free),
+            };
+            match asset {
+                AssetId::Tao => {
+                    let value: BalanceOf<T> = r.value.try_into().or(Err(Error::<T>::IllegalParameters))?;
+                    T::Currency::repatriate_reserved(&dominator, to, value, status)?;
+                    HostedTao::<T>::mutate(&dominator, |hosted| *hosted = hosted.saturating_sub(r.value));
+                }
+                AssetId::Token(id) => {
+                    let value: TokenBalanceOf<T> = r.value.try_into().or(Err(Error::<T>::IllegalParameters))?;
+                    T::Token::repatriate_reserved(&id, &dominator, to, value, status)?;
+                }
+            }
+            Self::remove_receipt(&fund_owner, &dominator, &asset);
+            match (target, asset) {
+                (Some(dest), _) => {
+                    Self::complete_migration(&fund_owner, &dest, &asset, r.value);
+                    migrated.push((dest, asset, r.value));
+                }
+                (None, AssetId::Tao) => tao = Some(r.value),
+                (None, AssetId::Token(id)) => tokens.push((id, r.value)),
+            }
+        }
+        Self::touch(&dominator, &claimed);
+        Self::deposit_event(RawEvent::AssetsClear(fund_owner, dominator, tao, tokens, migrated));
+        Ok(())
+    }
+
+    fn do_force_withdraw(origin: T::Origin, dominator: <T::Lookup as StaticLookup>::Source) -> DispatchResult {
+        let fund_owner = ensure_signed(origin)?;
+        let dominator = T::Lookup::lookup(dominator)?;
+        Self::settle_slash(&dominator, &fund_owner)?;
+        let mut claimed = Dominators::<T>::get(&dominator).ok_or(Error::<T>::DominatorNotFound)?;
+        let now = frame_system::Module::<T>::block_number();
+        let timeout = T::RevokeTimeout::get();
+        // all receipts are free to go once the dominator is banned or runs out of its grace period
+        let closed = match claimed.status {
+            DominatorStatus::Closing(t) => t.saturating_add(T::ClosingGracePeriod::get()) <= now,
+            DominatorStatus::Banned | DominatorStatus::Inactive => true,
+            _ => false,
+        };
+        let (expired, rest): (Vec<_>, Vec<_>) = HostedReceipts::<T>::iter_prefix((&fund_owner, &dominator))
+            .partition(|(_, r)| closed || r.revoking_since().map(|t| (*t).saturating_add(timeout) <= now).unwrap_or(false));
+        if expired.is_empty() {
+            ensure!(rest.iter().any(|(_, r)| r.is_revoking() || closed), Error::<T>::ReceiptNotExists);
+            return Err(Error::<T>::RevokingNotExpired.into());
+        }
+        let mut tao = None;
+        let mut tokens = vec![];
+        let mut migrated = vec![];
+        for (asset, r) in expired {
+            let target = Self::migration_target(&fund_owner, &dominator, &asset, r.value);
+            let (to, status) = match target.as_ref() {
+                Some(dest) => (dest, BalanceStatus::Reserved),
+                None => (&fund_owner, BalanceStatus::Free),
+            };
+            let moved = match asset {
+                AssetId::Tao => Self::force_repatriate_tao(&dominator, to, r.value, &mut claimed, status)?,
+                AssetId::Token(id) => Self::force_repatriate_token(&id, &dominator, to, r.value, status)?,
+            };
+            if moved < r.value {
+                Self::put_receipt(&fund_owner, &dominator, &asset, Receipt { value: r.value - moved, status: r.status });
+            } else {
+                Self::remove_receipt(&fund_owner, &dominator, &asset);
+            }
+            match (target, asset) {
+                (Some(dest), _) => {
+                    Self::complete_migration(&fund_owner, &dest, &asset, moved);
+                    migrated.push((dest, asset, moved));
+                }
+                (None, AssetId::Tao) => tao = Some(moved),
+                (None, AssetId::Token(id)) => tokens.push((id, moved)),
+            }
+        }
+        Dominators::<T>::insert(&dominator, &claimed);
+        Self::deposit_event(RawEvent::AssetsForceWithdrawn(fund_owner, dominator, tao, tokens, migrated));
+        Ok(())
+    }
+
+    fn do_claim_compensation(origin: T::Origin, dominator: <T::Lookup as StaticLookup>::Source) -> DispatchResult {
+        let fund_owner = ensure_signed(origin)?;
+        let dominator = T::Lookup::lookup(dominator)?;
+        let received = Self::settle_slash(&dominator, &fund_owner)?;
+        let owed = Compensations::<T>::get(&dominator, &fund_owner);
+        ensure!(received > 0 || owed > 0, Error::<T>::NoCompensation);
+        let paid = owed.min(Self::insurance_pot());
+        ensure!(received > 0 || paid > 0, Error::<T>::InsufficientInsurance);
+        if paid > 0 {
+            let value: BalanceOf<T> = paid.try_into().or(Err(Error::<T>::IllegalParameters))?;
+            T::Currency::transfer(&Self::account_id(), &fund_owner, value, ExistenceRequirement::AllowDeath)?;
+            if owed > paid {
+                Compensations::<T>::insert(&dominator, &fund_owner, owed - paid);
+            } else {
+                Compensations::<T>::remove(&dominator, &fund_owner);
+            }
+        }
+        Self::deposit_event(RawEvent::CompensationClaimed(dominator, fund_owner, received.saturating_add(paid)));
+        Ok(())
+    }
+
+    /// The dominator on whose behalf `who` acts, either itself or one of its operators.
+    /// Operators may sync, settle, confirm and answer challenges but never touch the pledge.
+    fn ensure_operator(who: T::AccountId) -> Result<T::AccountId, Error<T>> {
+        if Dominators::<T>::contains_key(&who) {
+            return Ok(who);
+        }
+        Self::operating_for(&who).ok_or(Error::<T>::DominatorNotFound)
+    }
+
+    /// The dominator `who` is an operator of.
+    fn operating_for(who: &T::AccountId) -> Option<T::AccountId> {
+        OperatorOf::<T>::get(who).filter(|dominator| Operators::<T>::get(dominator, who))
+    }
+
+    /// Record the activity of the dominator. What follows from it, i.e. recovery, insurance and
+    /// margin, is left to the liveness checks so the extrinsic at hand reports its own event only.
+    fn touch(dominator: &T::AccountId, claimed: &DominatorOf<T>) {
+        let now = frame_system::Module::<T>::block_number();
+        if !InsurancePaidAt::<T>::contains_key(dominator) {
+            InsurancePaidAt::<T>::insert(dominator, now);
+        }
+        LastActive::<T>::insert(dominator, now);
+        Self::schedule_liveness_check(dominator, now.saturating_add(T::LivenessWindow::get()));
+        if claimed.status == DominatorStatus::Inactive {
+            Self::schedule_liveness_check(dominator, now.saturating_add(One::one()));
+        }
+    }
+
+    fn schedule_liveness_check(dominator: &T::AccountId, at: T::BlockNumber) {
+        LivenessChecks::<T>::mutate(at, |due| {
+            if !due.contains(dominator) {
+                due.push(dominator.clone());
+            }
+        });
+    }
+
+    /// The TAO of the hosted assets. A token without a fresh price is valued at its last known
+    /// price, and left out if it has never been priced, so one token can't block the others.
+    fn hosted_value(dominator: &T::AccountId) -> Balance {
+        HostedTokens::<T>::get(dominator).iter().fold(
+            Self::hosted_tao(dominator),
+            |acc, id| {
+                let hosted: Balance = T::Token::reserved_balance(id, dominator)
+                    .try_into()
+                    .unwrap_or(Balance::max_value());
+                acc.saturating_add(Self::value_of(&AssetId::Token(*id), hosted).unwrap_or(0))
+            },
+        )
+    }
+
+    /// `None` if the token has never been priced.
+    fn value_of(asset: &AssetOf<T>, amount: Balance) -> Option<Balance> {
+        match asset {
+            _ if amount == 0 => Some(0),
+            AssetId::Tao => Some(amount),
+            AssetId::Token(id) => T::Prices::price(id)
+                .or_else(|| T::Prices::last_price(id))
+                .map(|price| {
+                    multiply_by_rational(amount, price, PRICE_UNIT).unwrap_or(Balance::max_value())
+                }),
+        }
+    }
+
+    /// The hosted value plus `extra` must stay within `PledgeMultiple` times `pledged`.
+    fn ensure_collateralized(
+        dominator: &T::AccountId,
+        pledged: Balance,
+        extra: Balance,
     ) -> Result<(), Error<T>> {
-        Receipts::<T>::mutate_exists((&owner, &dominator), |old| -> Result<(), Error<T>> {
-            let mut vec = old.take().unwrap_or(vec![]);
-            let mut target = match vec.iter().position(|r| filter(r)) {
-                Some(i) => Some(vec.swap_remove(i)),
-                None => None,
+        let value = Self::hosted_value(dominator);
+        let cap = pledged.saturating_mul(T::PledgeMultiple::get() as Balance);
+        ensure!(value.saturating_add(extra) <= cap, Error::<T>::CollateralInsufficient);
+        Ok(())
+    }
+
+    /// Open a margin call on an active dominator that has fallen below its pledge multiple, or
+    /// clear it once the pledge is restored.
+    fn check_margin(dominator: &T::AccountId, claimed: &DominatorOf<T>) {
+        if claimed.status != DominatorStatus::Active {
+            return;
+        }
+        let called = MarginCalls::<T>::contains_key(dominator);
+        match Self::ensure_collateralized(dominator, claimed.pledged, 0) {
+            Err(Error::<T>::CollateralInsufficient) if !called => {
+                let deadline = frame_system::Module::<T>::block_number()
+                    .saturating_add(T::MarginCallPeriod::get());
+                MarginCalls::<T>::insert(dominator, deadline);
+                MarginCallDeadlines::<T>::mutate(deadline, |due| due.push(dominator.clone()));
+                Self::deposit_event(RawEvent::MarginCalled(dominator.clone(), deadline));
+            }
+            Ok(()) if called => {
+                MarginCalls::<T>::remove(dominator);
+                Self::deposit_event(RawEvent::MarginCallCleared(dominator.clone()));
+            }
+            _ => {}
+        }
+    }
+
+    /// Close the dominator if its margin call is due and the pledge is still short.
+    fn close_margin_call(dominator: &T::AccountId, now: T::BlockNumber) {
+        if MarginCalls::<T>::get(dominator) != Some(now) {
+            return;
+        }
+        MarginCalls::<T>::remove(dominator);
+        let mut claimed = match Dominators::<T>::get(dominator) {
+            Some(claimed) if claimed.status == DominatorStatus::Active => claimed,
+            _ => return,
+        };
+        if Self::ensure_collateralized(dominator, claimed.pledged, 0).is_ok() {
+            Self::deposit_event(RawEvent::MarginCallCleared(dominator.clone()));
+            return;
+        }
+        claimed.status = DominatorStatus::Closing(now);
+        Dominators::<T>::insert(dominator, &claimed);
+        if !Self::try_close(dominator, &claimed) {
+            Self::deposit_event(RawEvent::DominatorClosing(dominator.clone(), now));
+        }
+    }
+
+    /// Collect the insurance fee and review the margin of the dominator, then deactivate it if it
+    /// has done nothing within `LivenessWindow`, or bring it back once it acts again.
+    fn check_liveness(dominator: &T::AccountId, now: T::BlockNumber) {
+        let (last, mut claimed) = match (LastActive::<T>::get(dominator), Dominators::<T>::get(dominator)) {
+            (Some(last), Some(claimed)) => (last, claimed),
+            _ => return,
+        };
+        if claimed.status != DominatorStatus::Banned {
+            Self::pay_insurance(dominator, &mut claimed, now);
+        }
+        let alive = last.saturating_add(T::LivenessWindow::get()) > now;
+        match claimed.status {
+            DominatorStatus::Active if !alive => {
+                claimed.status = DominatorStatus::Inactive;
+                Dominators::<T>::insert(dominator, &claimed);
+                Self::deposit_event(RawEvent::DominatorInactive(dominator.clone()));
+            }
+            DominatorStatus::Inactive if alive => {
+                claimed.status = DominatorStatus::Active;
+                Dominators::<T>::insert(dominator, &claimed);
+                Self::deposit_event(RawEvent::DominatorRecovered(dominator.clone()));
+            }
+            _ => {}
+        }
+        Self::check_margin(dominator, &claimed);
+    }
+
+    /// Append a new ledger root to the hash chain of the dominator.
+    fn commit(dominator: &T::AccountId, root: [u8; 32]) -> LedgerOf<T> {
+        let block = frame_system::Module::<T>::block_number();
+        Ledgers::<T>::mutate(dominator, |ledger| {
+            let next = match ledger.as_ref() {
+                Some(prev) => LedgerCommitment {
+                    root,
+                    seq: prev.seq.saturating_add(1),
+                    prev: T::Hashing::hash_of(prev),
+                    block,
+                },
+                None => LedgerCommitment {
+                    root,
+                    seq: 0,
+                    prev: Default::default(),
+                    block,
+                },
             };
-            mutator(&mut target)?;
-            match target {
-                Some(v) => vec.push(v),
-                None => {}
+            ledger.replace(next.clone());
+            next
+        })
+    }
+
+    /// The hosted TAO the dominator still holds, i.e. its reserve beyond the pledge, the profile
+    /// deposit and the slashed pledge kept for the fund owners, but no more than `HostedTao`. It falls short of `HostedTao` only if the reserve has
+    /// been taken by other means.
+    fn backing_tao(dominator: &T::AccountId, claimed: &DominatorOf<T>) -> Balance {
+        let reserved: Balance = T::Currency::reserved_balance(dominator)
+            .try_into()
+            .unwrap_or(Balance::max_value());
+        let kept = SlashPools::<T>::get(dominator).map(|pool| pool.funds).unwrap_or(0);
+        reserved
+            .saturating_sub(claimed.pledged)
+            .saturating_sub(ProfileDeposits::<T>::get(dominator))
+            .saturating_sub(kept)
+            .min(HostedTao::<T>::get(dominator))
+    }
+
+    /// Dominators without a profile take any asset, otherwise the asset must be listed and the
+    /// total hosted must stay within its cap. A token never priced can't be taken at all.
+    fn ensure_hostable(
+        dominator: &T::AccountId,
+        claimed: &DominatorOf<T>,
+        asset: &AssetOf<T>,
+        value: Balance,
+    ) -> Result<(), Error<T>> {
+        let extra = Self::value_of(asset, value).ok_or(Error::<T>::PriceNotAvailable)?;
+        let profile = match Profiles::<T>::get(dominator) {
+            Some(profile) => profile,
+            None => return Self::ensure_collateralized(dominator, claimed.pledged, extra),
+        };
+        let (hosted, cap) = match asset {
+            AssetId::Tao => (Self::hosted_tao(dominator), profile.tao_cap),
+            AssetId::Token(id) => {
+                let cap = profile
+                    .tokens
+                    .iter()
+                    .find(|(t, _)| t == id)
+                    .map(|(_, cap)| *cap)
+                    .ok_or(Error::<T>::TokenNotSupported)?;
+                let hosted: Balance = T::Token::reserved_balance(id, dominator)
+                    .try_into()
+                    .unwrap_or(Balance::max_value());
+                (hosted, Some(cap))
             }
-            old.replace(vec);
-            Ok(())
+        };
+        if let Some(cap) = cap {
+            ensure!(hosted.saturating_add(value) <= cap, Error::<T>::HostingCapExceeded);
+        }
+        Self::ensure_collateralized(dominator, claimed.pledged, extra)
+    }
+
+    /// The pledge and profile deposit are reserved along with the hosted TAO, so a receipt may only
+    /// take what is backing the receipts.
+    fn ensure_repatriable(
+        dominator: &T::AccountId,
+        claimed: &DominatorOf<T>,
+        asset: &AssetOf<T>,
+        value: Balance,
+    ) -> Result<(), Error<T>> {
+        match asset {
+            AssetId::Tao => {
+                ensure!(
+                    Self::backing_tao(dominator, claimed) >= value,
+                    Error::<T>::InsufficientStashAccount
+                );
+            }
+            AssetId::Token(id) => {
+                let value: TokenBalanceOf<T> =
+                    value.try_into().or(Err(Error::<T>::IllegalParameters))?;
+                ensure!(
+                    T::Token::reserved_balance(id, dominator) >= value,
+                    Error::<T>::InsufficientStashAccount
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the hosted TAO out of the dominator, taking the shortfall out of the pledge.
+    /// Returns the value actually moved, which is less than `value` only if the pledge runs out.
+    fn force_repatriate_tao(
+        dominator: &T::AccountId,
+        to: &T::AccountId,
+        value: Balance,
+        claimed: &mut DominatorOf<T>,
+        status: BalanceStatus,
+    ) -> Result<Balance, DispatchError> {
+        let stash = Self::backing_tao(dominator, claimed);
+        let hosted = value.min(stash);
+        // the rest comes out of the pledge, never the profile deposit
+        let shortfall = value.saturating_sub(stash).min(claimed.pledged);
+        let to_move: BalanceOf<T> = hosted
+            .saturating_add(shortfall)
+            .try_into()
+            .or(Err(Error::<T>::IllegalParameters))?;
+        let remaining =
+            T::Currency::repatriate_reserved(dominator, to, to_move, status)?;
+        claimed.pledged = claimed.pledged.saturating_sub(shortfall);
+        HostedTao::<T>::mutate(dominator, |h| *h = h.saturating_sub(hosted));
+        to_move
+            .saturating_sub(remaining)
+            .try_into()
+            .map_err(|_| Error::<T>::IllegalParameters.into())
+    }
+
+    /// Move the hosted token out of the dominator as much as it has reserved.
+    fn force_repatriate_token(
+        token: &T::TokenId,
+        dominator: &T::AccountId,
+        to: &T::AccountId,
+        value: Balance,
+        status: BalanceStatus,
+    ) -> Result<Balance, DispatchError> {
+        let value: TokenBalanceOf<T> = value.try_into().or(Err(Error::<T>::IllegalParameters))?;
+        let to_move = value.min(T::Token::reserved_balance(token, dominator));
+        if !to_move.is_zero() {
+            T::Token::repatriate_reserved(token, dominator, to, to_move, status)?;
+        }
+        to_move
+            .try_into()
+            .map_err(|_| Error::<T>::IllegalParameters.into())
+    }
+
+    /// The leaf of a fund owner in the ledger of a dominator, which is the blake2_256 of the encoded
+    /// `(fund_owner, tao, tokens)` where `tokens` are sorted by id.
+    pub fn ledger_leaf(fund_owner: &T::AccountId, dominator: &T::AccountId) -> [u8; 32] {
+        let mut tao: Balance = 0;
+        let mut tokens = vec![];
+        for (asset, r) in HostedReceipts::<T>::iter_prefix((fund_owner, dominator)) {
+            match asset {
+                AssetId::Tao => tao = r.value,
+                AssetId::Token(id) => tokens.push((id, r.value)),
+            }
+        }
+        tokens.sort_by_key(|(id, _)| *id);
+        sp_io::hashing::blake2_256(&(fund_owner, tao, tokens).encode())
+    }
+
+    /// The value of the receipts of the fund owner with the dominator, see `hosted_value`.
+    fn receipts_value(fund_owner: &T::AccountId, dominator: &T::AccountId) -> Balance {
+        HostedReceipts::<T>::iter_prefix((fund_owner, dominator)).fold(0, |acc, (asset, r)| {
+            acc.saturating_add(Self::value_of(&asset, r.value).unwrap_or(0))
         })
     }
 
-    fn try_mutate_receipt_exists(
+    /// Keep the leaf of the fund owner in the ledger just committed, which is what a challenge
+    /// is answered against.
+    fn commit_leaf(dominator: &T::AccountId, fund_owner: &T::AccountId, root: [u8; 32]) {
+        if !FundOwners::<T>::contains_key(dominator, fund_owner) {
+            return;
+        }
+        CommittedLeaves::<T>::insert(
+            dominator,
+            fund_owner,
+            CommittedLeaf {
+                root,
+                leaf: Self::ledger_leaf(fund_owner, dominator),
+                value: Self::receipts_value(fund_owner, dominator),
+            },
+        );
+    }
+
+    fn fail_challenge(dominator: &T::AccountId, fund_owner: &T::AccountId, now: T::BlockNumber) {
+        // answered, or answered and raised again since it was scheduled
+        let challenge = match Challenges::<T>::get(dominator, fund_owner) {
+            Some(challenge) if challenge.deadline <= now => challenge,
+            _ => return,
+        };
+        Challenges::<T>::remove(dominator, fund_owner);
+        let deposit: BalanceOf<T> = T::ChallengeDeposit::get().try_into().unwrap_or_else(|_| Zero::zero());
+        T::Currency::unreserve(fund_owner, deposit);
+        Self::deposit_event(RawEvent::ChallengeFailed(dominator.clone(), fund_owner.clone()));
+        // the slash is what the dominator failed to prove, nothing is left once an earlier
+        // challenge has taken the whole pledge
+        if Dominators::<T>::get(dominator).map(|d| d.pledged > 0).unwrap_or(false) {
+            let _ = Self::do_slash(dominator, challenge.value);
+        }
+    }
+
+    /// Slash and ban the dominator. As much of the slash as the fund owners are short of is kept
+    /// reserved in its slash pool for them to claim, see `settle_slash`, the rest goes to
+    /// `T::Slash`. Whatever the pool can't cover is claimable from the insurance pot, up to
+    /// `MaxCompensationPerIncident`.
+    fn do_slash(dominator: &T::AccountId, amount: Balance) -> DispatchResult {
+        let mut claimed = Dominators::<T>::get(dominator).ok_or(Error::<T>::DominatorNotFound)?;
+        // a pool is set up once and topped up by later slashes until it is fully claimed
+        let mut pool = match SlashPools::<T>::get(dominator) {
+            Some(pool) if pool.owed > 0 => pool,
+            last => {
+                let shortfalls = Self::shortfalls(dominator, &claimed);
+                SlashPool {
+                    id: last.map(|pool| pool.id.saturating_add(1)).unwrap_or(0),
+                    owed: shortfalls
+                        .iter()
+                        .fold(0u128, |acc, (_, _, _, value)| acc.saturating_add(*value)),
+                    shortfalls,
+                    funds: 0,
+                    compensation: 0,
+                }
+            }
+        };
+        let amount = amount.min(claimed.pledged);
+        let kept = amount.min(pool.owed.saturating_sub(pool.funds));
+        let value: BalanceOf<T> = (amount - kept)
+            .try_into()
+            .or(Err(Error::<T>::IllegalParameters))?;
+        let (imbalance, _) = T::Currency::slash_reserved(dominator, value);
+        let slashed: Balance = imbalance
+            .peek()
+            .try_into()
+            .or(Err(Error::<T>::IllegalParameters))?;
+        T::Slash::on_unbalanced(imbalance);
+        let slashed = slashed.saturating_add(kept);
+        claimed.pledged = claimed.pledged.saturating_sub(slashed);
+        let banned = claimed.status != DominatorStatus::Banned;
+        claimed.status = DominatorStatus::Banned;
+        Dominators::<T>::insert(dominator, &claimed);
+        pool.funds = pool.funds.saturating_add(kept);
+        pool.compensation = pool
+            .owed
+            .saturating_sub(pool.funds)
+            .min(T::MaxCompensationPerIncident::get());
+        if pool.owed > 0 {
+            SlashPools::<T>::insert(dominator, &pool);
+        }
+        if banned {
+            Self::deposit_event(RawEvent::DominatorBanned(dominator.clone(), slashed, pool.compensation));
+        } else {
+            Self::deposit_event(RawEvent::DominatorSlashed(dominator.clone(), slashed, pool.compensation));
+        }
+        Ok(())
+    }
+
+    /// The amount of each asset the dominator can't back of its receipts as `(asset, short,
+    /// total, value)`, see `SlashPool`.
+    fn shortfalls(
+        dominator: &T::AccountId,
+        claimed: &DominatorOf<T>,
+    ) -> Vec<(AssetOf<T>, Balance, Balance, Balance)> {
+        sp_std::iter::once(AssetId::Tao)
+            .chain(HostedTokens::<T>::get(dominator).into_iter().map(AssetId::Token))
+            .filter_map(|asset| {
+                let total = ReceiptTotals::<T>::get(dominator, &asset);
+                let hosted = match asset {
+                    AssetId::Tao => Self::backing_tao(dominator, claimed),
+                    AssetId::Token(id) => T::Token::reserved_balance(&id, dominator)
+                        .try_into()
+                        .unwrap_or(Balance::max_value()),
+                };
+                let short = total.saturating_sub(hosted);
+                if short == 0 {
+                    return None;
+                }
+                let value = Self::value_of(&asset, short).unwrap_or(0);
+                Some((asset, short, total, value))
+            })
+            .collect()
+    }
+
+    /// Settle the share of the fund owner in the slash pool of the dominator, once per pool: its
+    /// receipts are reduced by their part of the shortfalls, it is paid as much of the kept
+    /// pledge and owed as much of the compensation. Returns the pledge paid.
+    fn settle_slash(dominator: &T::AccountId, fund_owner: &T::AccountId) -> Result<Balance, DispatchError> {
+        let mut pool = match SlashPools::<T>::get(dominator) {
+            Some(pool) if pool.owed > 0 && FundOwners::<T>::contains_key(dominator, fund_owner) => pool,
+            _ => return Ok(0),
+        };
+        if SlashClaims::<T>::get(dominator, fund_owner) == Some(pool.id) {
+            return Ok(0);
+        }
+        SlashClaims::<T>::insert(dominator, fund_owner, pool.id);
+        let mut share: Balance = 0;
+        for (asset, short, total, value) in pool.shortfalls.iter() {
+            let r = match HostedReceipts::<T>::get((fund_owner, dominator), asset) {
+                Some(r) if r.value > 0 => r,
+                _ => continue,
+            };
+            let debit = multiply_by_rational(*short, r.value, *total).unwrap_or(0).min(r.value);
+            share = share.saturating_add(multiply_by_rational(*value, r.value, *total).unwrap_or(0));
+            Self::put_receipt(fund_owner, dominator, asset, Receipt { value: r.value - debit, status: r.status });
+            // the TAO lost from the reserve is no longer hosted
+            if asset == &AssetId::Tao {
+                HostedTao::<T>::mutate(dominator, |h| *h = h.saturating_sub(debit));
+            }
+        }
+        let share = share.min(pool.owed);
+        let pay = multiply_by_rational(pool.funds, share, pool.owed).unwrap_or(0);
+        let compensation = multiply_by_rational(pool.compensation, share, pool.owed).unwrap_or(0);
+        pool.owed -= share;
+        pool.funds = pool.funds.saturating_sub(pay);
+        pool.compensation = pool.compensation.saturating_sub(compensation);
+        if pay > 0 {
+            let value: BalanceOf<T> = pay.try_into().or(Err(Error::<T>::IllegalParameters))?;
+            T::Currency::repatriate_reserved(dominator, fund_owner, value, BalanceStatus::Free)?;
+        }
+        if compensation > 0 {
+            Compensations::<T>::mutate(dominator, fund_owner, |c| *c = c.saturating_add(compensation));
+        }
+        // the rounding left once everyone has claimed goes the way of the rest of the slash
+        if pool.owed == 0 && pool.funds > 0 {
+            let dust: BalanceOf<T> = pool.funds.try_into().unwrap_or_else(|_| Zero::zero());
+            let (imbalance, _) = T::Currency::slash_reserved(dominator, dust);
+            T::Slash::on_unbalanced(imbalance);
+            pool.funds = 0;
+            pool.compensation = 0;
+        }
+        SlashPools::<T>::insert(dominator, &pool);
+        Ok(pay)
+    }
+
+    /// The account of the insurance pot.
+    pub fn account_id() -> T::AccountId {
+        T::ModuleId::get().into_account()
+    }
+
+    /// The TAO in the insurance pot.
+    pub fn insurance_pot() -> Balance {
+        T::Currency::free_balance(&Self::account_id())
+            .try_into()
+            .unwrap_or(Balance::max_value())
+    }
+
+    /// Charge `InsuranceFeeRate` of the hosted value for each `InsurancePeriod` passed since the
+    /// last payment, out of the free balance of the dominator or else its pledge.
+    fn pay_insurance(dominator: &T::AccountId, claimed: &mut DominatorOf<T>, now: T::BlockNumber) {
+        let last = match InsurancePaidAt::<T>::get(dominator) {
+            Some(last) => last,
+            None => {
+                InsurancePaidAt::<T>::insert(dominator, now);
+                return;
+            }
+        };
+        let period = T::InsurancePeriod::get();
+        if period.is_zero() {
+            return;
+        }
+        let periods = now.saturating_sub(last) / period;
+        if periods.is_zero() {
+            return;
+        }
+        InsurancePaidAt::<T>::insert(
+            dominator,
+            last.saturating_add(periods.saturating_mul(period)),
+        );
+        let fee = (T::InsuranceFeeRate::get() * Self::hosted_value(dominator))
+            .saturating_mul(periods.saturated_into::<Balance>());
+        let value: BalanceOf<T> = match fee.try_into() {
+            Ok(value) if fee > 0 => value,
+            _ => return,
+        };
+        let imbalance = match T::Currency::withdraw(
+            dominator,
+            value,
+            WithdrawReason::Fee.into(),
+            ExistenceRequirement::KeepAlive,
+        ) {
+            Ok(imbalance) => imbalance,
+            Err(_) => {
+                let value: BalanceOf<T> =
+                    fee.min(claimed.pledged).try_into().unwrap_or_else(|_| Zero::zero());
+                let (imbalance, _) = T::Currency::slash_reserved(dominator, value);
+                let taken: Balance = imbalance.peek().try_into().unwrap_or(0);
+                claimed.pledged = claimed.pledged.saturating_sub(taken);
+                Dominators::<T>::insert(dominator, &*claimed);
+                imbalance
+            }
+        };
+        let paid: Balance = imbalance.peek().try_into().unwrap_or(0);
+        T::Currency::resolve_creating(&Self::account_id(), imbalance);
+        Self::deposit_event(RawEvent::InsurancePaid(dominator.clone(), paid));
+    }
+
+    /// Release the pledge and remove the dominator once it is closing and hosts nothing.
+    fn try_close(dominator: &T::AccountId, claimed: &DominatorOf<T>) -> bool {
+        if !matches!(claimed.status, DominatorStatus::Closing(_)) || Hostings::<T>::get(dominator) > 0 {
+            return false;
+        }
+        let pledged: BalanceOf<T> = claimed.pledged.try_into().unwrap_or_else(|_| Zero::zero());
+        T::Currency::unreserve(dominator, pledged);
+        let deposit: BalanceOf<T> = ProfileDeposits::<T>::take(dominator).try_into().unwrap_or_else(|_| Zero::zero());
+        T::Currency::unreserve(dominator, deposit);
+        Profiles::<T>::remove(dominator);
+        LastActive::<T>::remove(dominator);
+        InsurancePaidAt::<T>::remove(dominator);
+        HostedTokens::<T>::remove(dominator);
+        MarginCalls::<T>::remove(dominator);
+        Dominators::<T>::remove(dominator);
+        Hostings::<T>::remove(dominator);
+        HostedTao::<T>::remove(dominator);
+        Operators::<T>::remove_prefix(dominator);
+        Self::deposit_event(RawEvent::DominatorClosed(dominator.clone(), claimed.pledged));
+        true
+    }
+
+    fn put_receipt(
         owner: &T::AccountId,
         dominator: &T::AccountId,
-        filter: impl Fn(&ReceiptOf<T>) -> bool,
+        asset: &AssetOf<T>,
+        receipt: ReceiptOf<T>,
+    ) {
+        HostedReceipts::<T>::mutate((owner, dominator), asset, |old| {
+            if old.is_none() {
+                Hostings::<T>::mutate(dominator, |n| *n = n.saturating_add(1));
+                FundOwners::<T>::mutate(dominator, owner, |n| *n = n.saturating_add(1));
+                if let AssetId::Token(id) = asset {
+                    HostedTokens::<T>::mutate(dominator, |tokens| tokens.insert(*id));
+                }
+            }
+            let before = old.as_ref().map(|r| r.value).unwrap_or(0);
+            Self::adjust_total(dominator, asset, before, receipt.value);
+            old.replace(receipt);
+        });
+    }
+
+    fn adjust_total(dominator: &T::AccountId, asset: &AssetOf<T>, before: Balance, after: Balance) {
+        ReceiptTotals::<T>::mutate_exists(dominator, asset, |total| {
+            let t = total.unwrap_or(0).saturating_sub(before).saturating_add(after);
+            *total = Some(t).filter(|t| *t > 0)
+        });
+    }
+
+    fn remove_receipt(owner: &T::AccountId, dominator: &T::AccountId, asset: &AssetOf<T>) {
+        if let Some(r) = HostedReceipts::<T>::take((owner, dominator), asset) {
+            Self::adjust_total(dominator, asset, r.value, 0);
+            Hostings::<T>::mutate(dominator, |n| *n = n.saturating_sub(1));
+            FundOwners::<T>::mutate_exists(dominator, owner, |n| {
+                *n = n.map(|n| n.saturating_sub(1)).filter(|n| *n > 0)
+            });
+            if !FundOwners::<T>::contains_key(dominator, owner) {
+                CommittedLeaves::<T>::remove(dominator, owner);
+                SlashClaims::<T>::remove(dominator, owner);
+            }
+        }
+        Migrations::<T>::remove((owner, dominator), asset);
+    }
+
+    /// The destination of a pending migration, unless it no longer takes the asset.
+    fn migration_target(
+        fund_owner: &T::AccountId,
+        dominator: &T::AccountId,
+        asset: &AssetOf<T>,
+        value: Balance,
+    ) -> Option<T::AccountId> {
+        Migrations::<T>::get((fund_owner, dominator), asset).filter(|dest| {
+            let hostable = Dominators::<T>::get(dest)
+                .filter(|d| d.status == DominatorStatus::Active)
+                .map(|d| Self::ensure_hostable(dest, &d, asset, value).is_ok())
+                .unwrap_or(false);
+            let revoking = HostedReceipts::<T>::get((fund_owner, dest), asset)
+                .map(|r| r.is_revoking())
+                .unwrap_or(false);
+            hostable && !revoking
+        })
+    }
+
+    /// Credit the destination with the value already repatriated to its reserved balance.
+    fn complete_migration(
+        fund_owner: &T::AccountId,
+        to: &T::AccountId,
+        asset: &AssetOf<T>,
+        value: Balance,
+    ) {
+        let credited = HostedReceipts::<T>::get((fund_owner, to), asset)
+            .map(|r| r.value)
+            .unwrap_or(0)
+            .saturating_add(value);
+        Self::put_receipt(fund_owner, to, asset, Receipt::new(credited));
+        if asset == &AssetId::Tao {
+            HostedTao::<T>::mutate(to, |hosted| *hosted = hosted.saturating_add(value));
+        }
+    }
+
+    fn try_mutate_receipt(
+        owner: &T::AccountId,
+        dominator: &T::AccountId,
+        asset: &AssetOf<T>,
         mutator: impl FnOnce(&mut Option<ReceiptOf<T>>) -> Result<(), Error<T>>,
     ) -> Result<(), Error<T>> {
-        Receipts::<T>::mutate_exists((&owner, &dominator), |old| -> Result<(), Error<T>> {
-            let mut vec = old.take().unwrap_or(vec![]);
-            let mut target = match vec.iter().position(|r| filter(r)) {
-                Some(i) => Some(vec.swap_remove(i)),
-                None => None,
-            };
-            if target.is_none() {
-                return Err(Error::<T>::ReceiptNotExists);
+        let mut target = HostedReceipts::<T>::get((owner, dominator), asset);
+        mutator(&mut target)?;
+        match target {
+            Some(r) => Self::put_receipt(owner, dominator, asset, r),
+            None => Self::remove_receipt(owner, dominator, asset),
+        }
+        Ok(())
+    }
+
+    /// Overwrite the value of an existing receipt with the one in the ledger of the dominator,
+    /// returning the old and new value if the receipt exists.
+    fn sync_receipt(
+        owner: &T::AccountId,
+        dominator: &T::AccountId,
+        asset: &AssetOf<T>,
+        value: Balance,
+    ) -> Option<(Balance, Balance)> {
+        match HostedReceipts::<T>::get((owner, dominator), asset) {
+            Some(r) if r.value != 0 => {
+                Self::put_receipt(
+                    owner,
+                    dominator,
+                    asset,
+                    Receipt {
+                        value,
+                        status: r.status,
+                    },
+                );
+                Some((r.value, value))
             }
-            mutator(&mut target)?;
-            match target {
-                Some(v) => vec.push(v),
-                None => {}
+            Some(_) => {
+                Self::remove_receipt(owner, dominator, asset);
+                Some((0, 0))
             }
-            old.replace(vec);
+            None => None,
+        }
+    }
+
+    /// The hosted value of each asset must add up to the same before and after the changes,
+    /// each receipt may only be touched once.
+    fn ensure_conserved(
+        dominator: &T::AccountId,
+        changes: &[(T::AccountId, AssetOf<T>, Balance)],
+    ) -> Result<(), Error<T>> {
+        let mut touched = BTreeSet::new();
+        let mut sums: BTreeMap<AssetOf<T>, (Balance, Balance)> = BTreeMap::new();
+        for (fund_owner, asset, value) in changes {
+            ensure!(touched.insert((fund_owner, asset)), Error::<T>::IllegalParameters);
+            let before = HostedReceipts::<T>::get((fund_owner, dominator), asset)
+                .map(|r| r.value)
+                .unwrap_or(0);
+            // see `sync_receipt`, only non-zero receipts take the new value
+            let after = if before == 0 { 0 } else { *value };
+            let sum = sums.entry(*asset).or_insert((0, 0));
+            sum.0 = sum.0.checked_add(before).ok_or(Error::<T>::IllegalParameters)?;
+            sum.1 = sum.1.checked_add(after).ok_or(Error::<T>::IllegalParameters)?;
+        }
+        ensure!(sums.values().all(|(before, after)| before == after), Error::<T>::UnbalancedSync);
+        Ok(())
+    }
+
+    /// Move value between the active receipts of two fund owners of the dominator.
+    fn transfer_receipt(
+        dominator: &T::AccountId,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        asset: &AssetOf<T>,
+        value: Balance,
+    ) -> Result<(), Error<T>> {
+        if value == 0 {
+            return Ok(());
+        }
+        Self::debit_receipt(from, dominator, asset, value)?;
+        Self::try_mutate_receipt(to, dominator, asset, |exists| {
+            let r = exists.get_or_insert(Receipt::new(0));
+            ensure!(r.is_active(), Error::<T>::InvalidStatus);
+            r.value = r.value.checked_add(value).ok_or(Error::<T>::IllegalParameters)?;
             Ok(())
         })
     }
+
+    fn debit_receipt(
+        owner: &T::AccountId,
+        dominator: &T::AccountId,
+        asset: &AssetOf<T>,
+        value: Balance,
+    ) -> Result<(), Error<T>> {
+        Self::try_mutate_receipt(owner, dominator, asset, |exists| {
+            let r = exists
+                .as_mut()
+                .filter(|r| r.is_active())
+                .ok_or(Error::<T>::InsufficientReceipt)?;
+            r.value = r.value.checked_sub(value).ok_or(Error::<T>::InsufficientReceipt)?;
+            Ok(())
+        })
+    }
+
+    /// The fee leaves the receipts for the free balance of the dominator, so it neither keeps
+    /// the dominator open nor gets compensated.
+    fn charge_fee(
+        dominator: &T::AccountId,
+        taker: &T::AccountId,
+        asset: &AssetOf<T>,
+        fee: Balance,
+    ) -> DispatchResult {
+        if fee == 0 {
+            return Ok(());
+        }
+        Self::debit_receipt(taker, dominator, asset, fee)?;
+        match asset {
+            AssetId::Tao => {
+                let value: BalanceOf<T> = fee.try_into().or(Err(Error::<T>::IllegalParameters))?;
+                T::Currency::unreserve(dominator, value);
+                HostedTao::<T>::mutate(dominator, |hosted| *hosted = hosted.saturating_sub(fee));
+            }
+            AssetId::Token(id) => {
+                let value: TokenBalanceOf<T> = fee.try_into().or(Err(Error::<T>::IllegalParameters))?;
+                T::Token::unreserve(id, dominator, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Keep the memo of a grant until `MemoExpiry` blocks later, so a retried grant can't credit twice.
+    fn record_memo(fund_owner: &T::AccountId, dominator: &T::AccountId, memo: UID) {
+        let expiry = frame_system::Module::<T>::block_number().saturating_add(T::MemoExpiry::get());
+        Memos::<T>::insert((fund_owner, dominator), memo, expiry);
+        MemoExpirations::<T>::mutate(expiry, |memos| {
+            memos.push(((fund_owner.clone(), dominator.clone()), memo))
+        });
+    }
+
+    /// Mark an active receipt as revoking, or drop it if nothing is left in it.
+    fn revoke_receipt(
+        owner: &T::AccountId,
+        dominator: &T::AccountId,
+        asset: &AssetOf<T>,
+        at: T::BlockNumber,
+    ) -> Result<Balance, Error<T>> {
+        let exists = HostedReceipts::<T>::get((owner, dominator), asset)
+            .filter(|r| r.is_active())
+            .ok_or(Error::<T>::ReceiptNotExists)?;
+        if exists.value == 0 {
+            Self::remove_receipt(owner, dominator, asset);
+        } else {
+            Self::put_receipt(
+                owner,
+                dominator,
+                asset,
+                Receipt {
+                    value: exists.value,
+                    status: ReceiptStatus::Revoking(at),
+                },
+            );
+        }
+        Ok(exists.value)
+    }
+
+    /// Move the receipts out of `Vec<LegacyReceipt>` into one entry per asset, counted along in
+    /// `Hostings`, `FundOwners`, `ReceiptTotals` and `HostedTao`. The closing dominators get their
+    /// grace period from now on, and the liveness of all dominators is tracked from now on.
+    fn migrate_to_v2() -> Weight {
+        let legacy = StorageIterator::<Vec<LegacyReceipt<T::TokenId, T::BlockNumber>>>::new(
+            b"Receipts",
+            b"Receipts",
+        )
+        .drain()
+        .collect::<Vec<_>>();
+        let mut migrated: Weight = 0;
+        for (key, receipts) in legacy {
+            // the key is hashed by blake2_128_concat, i.e. 16 bytes of hash followed by the pair
+            let pair = match key
+                .get(16..)
+                .and_then(|mut raw| HostingPair::<T>::decode(&mut raw).ok())
+            {
+                Some(pair) => pair,
+                None => continue,
+            };
+            for r in receipts {
+                let (asset, receipt) = match r {
+                    LegacyReceipt::Tao(value, status) => {
+                        HostedTao::<T>::mutate(&pair.1, |hosted| *hosted = hosted.saturating_add(value));
+                        (AssetId::Tao, Receipt { value, status })
+                    }
+                    LegacyReceipt::Token(id, value, status) => {
+                        (AssetId::Token(id), Receipt { value, status })
+                    }
+                };
+                Self::put_receipt(&pair.0, &pair.1, &asset, receipt);
+                migrated = migrated.saturating_add(1);
+            }
+        }
+        let now = frame_system::Module::<T>::block_number();
+        Dominators::<T>::translate::<LegacyDominator, _>(|_, old| {
+            let status = match old.status {
+                LegacyDominatorStatus::Active => DominatorStatus::Active,
+                LegacyDominatorStatus::Closing => DominatorStatus::Closing(now),
+                LegacyDominatorStatus::Banned => DominatorStatus::Banned,
+            };
+            Some(Dominator {
+                pledged: old.pledged,
+                status,
+            })
+        });
+        let mut dominators: Weight = 0;
+        for (dominator, claimed) in Dominators::<T>::iter() {
+            Self::touch(&dominator, &claimed);
+            dominators = dominators.saturating_add(1);
+        }
+        StorageVersion::put(Releases::V2);
+        T::DbWeight::get().reads_writes(
+            migrated.saturating_add(dominators.saturating_mul(2)).saturating_add(1),
+            migrated.saturating_mul(4).saturating_add(dominators.saturating_mul(3)).saturating_add(1),
+        )
+    }
 }
@@ -0,0 +1,243 @@
+// Copyright 2021 UINB Technologies Pte. Ltd.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `impl_outer_origin` implements `Into` rather than `From`
+#![allow(clippy::from_over_into)]
+
+use crate::{GenesisConfig, Module, Trait, PRICE_UNIT};
+use frame_support::traits::{OnFinalize, OnInitialize};
+use frame_support::{impl_outer_event, impl_outer_origin, parameter_types, weights::Weight};
+use frame_system as system;
+use fuso_support::traits::PriceProvider;
+use pallet_balances as balances;
+use sp_core::H256;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+    ModuleId, Perbill,
+};
+use std::{cell::RefCell, collections::BTreeMap};
+
+pub const ALICE: <Test as system::Trait>::AccountId = 1;
+pub const BOB: <Test as system::Trait>::AccountId = 2;
+pub const CHRIS: <Test as system::Trait>::AccountId = 3;
+pub const DAVE: <Test as system::Trait>::AccountId = 4;
+
+impl_outer_origin! {
+    pub enum Origin for Test where system = frame_system {}
+}
+
+mod receipts {
+    pub use crate::Event;
+}
+
+impl_outer_event! {
+    pub enum TestEvent for Test {
+        system<T>,
+        balances<T>,
+        fuso_pallet_token<T>,
+        receipts<T>,
+    }
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const MaximumBlockWeight: Weight = 1024;
+    pub const MaximumBlockLength: u32 = 2 * 1024;
+    pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+    pub const ExistentialDeposit: u128 = 1;
+    pub const RevokeTimeout: u64 = 10;
+    pub const ClosingGracePeriod: u64 = 20;
+    pub const MinimumPledge: u128 = 100;
+    pub const ChallengeWindow: u64 = 5;
+    pub const ChallengeDeposit: u128 = 10;
+    pub const MaxChallengesPerBlock: u32 = 1;
+    pub const MaxSyncBatch: u32 = 4;
+    pub const MemoExpiry: u64 = 5;
+    pub const MaxProfileLength: u32 = 32;
+    pub const MaxSupportedTokens: u32 = 4;
+    pub const ProfileDepositBase: u128 = 10;
+    pub const ProfileDepositPerByte: u128 = 1;
+    pub const LivenessWindow: u64 = 30;
+    pub const MaxLivenessChecks: u32 = 1;
+    pub const ReceiptsModuleId: ModuleId = ModuleId(*b"fuso/rcp");
+    pub const InsuranceFeeRate: Perbill = Perbill::from_percent(10);
+    pub const InsurancePeriod: u64 = 10;
+    pub const MaxCompensationPerIncident: u128 = 500;
+    pub const PledgeMultiple: u32 = 10;
+    pub const MarginCallPeriod: u64 = 10;
+}
+
+thread_local! {
+    static PRICES: RefCell<BTreeMap<u32, Option<u128>>> = RefCell::new(BTreeMap::new());
+    static LAST_PRICES: RefCell<BTreeMap<u32, u128>> = RefCell::new(BTreeMap::new());
+}
+
+/// Every token is worth 1 TAO unless set otherwise.
+pub struct Prices;
+
+impl Prices {
+    /// `None` makes the price stale, the last one set is kept as the last known price.
+    pub fn set(token: u32, price: Option<u128>) {
+        PRICES.with(|p| p.borrow_mut().insert(token, price));
+        if let Some(price) = price {
+            LAST_PRICES.with(|p| p.borrow_mut().insert(token, price));
+        }
+    }
+}
+
+impl PriceProvider<u32> for Prices {
+    fn price(token: &u32) -> Option<u128> {
+        PRICES.with(|p| p.borrow().get(token).cloned().unwrap_or(Some(PRICE_UNIT)))
+    }
+
+    fn last_price(token: &u32) -> Option<u128> {
+        Self::price(token).or_else(|| LAST_PRICES.with(|p| p.borrow().get(token).cloned()))
+    }
+}
+
+impl system::Trait for Test {
+    type BaseCallFilter = ();
+    type Origin = Origin;
+    type Call = ();
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = TestEvent;
+    type BlockHashCount = BlockHashCount;
+    type MaximumBlockWeight = MaximumBlockWeight;
+    type DbWeight = ();
+    type BlockExecutionWeight = ();
+    type ExtrinsicBaseWeight = ();
+    type MaximumExtrinsicWeight = MaximumBlockWeight;
+    type MaximumBlockLength = MaximumBlockLength;
+    type AvailableBlockRatio = AvailableBlockRatio;
+    type Version = ();
+    type PalletInfo = ();
+    type AccountData = pallet_balances::AccountData<u128>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+}
+
+impl balances::Trait for Test {
+    type Balance = u128;
+    type MaxLocks = ();
+    type Event = TestEvent;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+impl fuso_pallet_token::Trait for Test {
+    type Event = TestEvent;
+    type Balance = u128;
+    type TokenId = u32;
+    type SymbolOrigin = frame_system::EnsureRoot<u64>;
+}
+
+impl Trait for Test {
+    type Event = TestEvent;
+    type Currency = Balances;
+    type TokenId = u32;
+    type Token = Token;
+    type RevokeTimeout = RevokeTimeout;
+    type ClosingGracePeriod = ClosingGracePeriod;
+    type SlashOrigin = frame_system::EnsureRoot<u64>;
+    type Slash = ReceiptsModule;
+    type MinimumPledge = MinimumPledge;
+    type ChallengeWindow = ChallengeWindow;
+    type ChallengeDeposit = ChallengeDeposit;
+    type MaxChallengesPerBlock = MaxChallengesPerBlock;
+    type MaxSyncBatch = MaxSyncBatch;
+    type MemoExpiry = MemoExpiry;
+    type MaxProfileLength = MaxProfileLength;
+    type MaxSupportedTokens = MaxSupportedTokens;
+    type ProfileDepositBase = ProfileDepositBase;
+    type ProfileDepositPerByte = ProfileDepositPerByte;
+    type LivenessWindow = LivenessWindow;
+    type MaxLivenessChecks = MaxLivenessChecks;
+    type ModuleId = ReceiptsModuleId;
+    type InsuranceFeeRate = InsuranceFeeRate;
+    type InsurancePeriod = InsurancePeriod;
+    type MaxCompensationPerIncident = MaxCompensationPerIncident;
+    type Prices = Prices;
+    type PledgeMultiple = PledgeMultiple;
+    type MarginCallPeriod = MarginCallPeriod;
+}
+
+pub type ReceiptsModule = Module<Test>;
+pub type System = frame_system::Module<Test>;
+pub type Balances = pallet_balances::Module<Test>;
+pub type Token = fuso_pallet_token::Module<Test>;
+
+pub fn last_event() -> TestEvent {
+    System::events().pop().expect("event expected").event
+}
+
+/// The events of the receipts module only, leaving out e.g. the accounts created by transfers.
+pub fn receipts_events() -> Vec<crate::Event<Test>> {
+    System::events()
+        .into_iter()
+        .filter_map(|record| match record.event {
+            TestEvent::receipts(event) => Some(event),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn run_to_block(block: u64) {
+    while System::block_number() < block {
+        ReceiptsModule::on_finalize(System::block_number());
+        System::on_finalize(System::block_number());
+        System::set_block_number(System::block_number() + 1);
+        System::on_initialize(System::block_number());
+        ReceiptsModule::on_initialize(System::block_number());
+    }
+}
+
+pub fn receipts_test_ext() -> sp_io::TestExternalities {
+    // the tests may share a thread
+    PRICES.with(|p| p.borrow_mut().clear());
+    LAST_PRICES.with(|p| p.borrow_mut().clear());
+    let mut t = system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+
+    balances::GenesisConfig::<Test> {
+        balances: vec![
+            (ALICE, 1_000_000),
+            (BOB, 1_000_000),
+            (CHRIS, 1_000_000),
+            (DAVE, 1_000_000),
+        ],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    GenesisConfig::default()
+        .assimilate_storage(&mut t)
+        .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(t);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
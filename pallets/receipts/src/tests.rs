@@ -0,0 +1,1473 @@
+// Copyright 2021 UINB Technologies Pte. Ltd.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::mock::*;
+use crate::*;
+use frame_support::{
+    assert_noop, assert_ok,
+    storage::migration::put_storage_value,
+    traits::OnRuntimeUpgrade,
+    Blake2_128Concat, StorageHasher,
+};
+use sp_runtime::traits::BlakeTwo256;
+
+fn issue_token(owner: u64, symbol: &[u8], total: u128) -> u32 {
+    let id = Token::next_token_id();
+    assert_ok!(Token::issue(
+        Origin::signed(owner),
+        total,
+        symbol.to_vec(),
+        6,
+        symbol.to_vec(),
+        None,
+        0
+    ));
+    id
+}
+
+#[test]
+fn test_confirm_revoking_receipts() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(ALICE, b"USDT", 10_000);
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt,
+            2000,
+            2
+        ));
+        assert_eq!(Balances::free_balance(&ALICE), 999_500);
+        assert_eq!(Balances::reserved_balance(&BOB), 1500);
+        assert_eq!(Token::reserved_balance(&usdt, &BOB), 2000);
+
+        // nothing to confirm yet
+        assert_noop!(
+            ReceiptsModule::confirm(Origin::signed(BOB), ALICE),
+            Error::<Test>::ReceiptNotExists
+        );
+
+        assert_ok!(ReceiptsModule::withdraw(Origin::signed(ALICE), BOB));
+        assert_ok!(ReceiptsModule::confirm(Origin::signed(BOB), ALICE));
+        assert_eq!(Balances::free_balance(&ALICE), 1_000_000);
+        assert_eq!(Balances::reserved_balance(&BOB), 1000);
+        assert_eq!(ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Tao), None);
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Token(usdt)),
+            Some(Receipt {
+                value: 2000,
+                status: ReceiptStatus::Active
+            })
+        );
+
+        assert_ok!(ReceiptsModule::withdraw_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt
+        ));
+        assert_ok!(ReceiptsModule::confirm(Origin::signed(BOB), ALICE));
+        assert_eq!(Token::free_balance(&usdt, &ALICE), 10_000);
+        assert_eq!(Token::reserved_balance(&usdt, &BOB), 0);
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Token(usdt)),
+            None
+        );
+        assert_eq!(ReceiptsModule::hostings(&BOB), 0);
+    });
+}
+
+#[test]
+fn test_confirm_should_not_touch_pledge() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        // the dominator has lost part of what it is hosting
+        let _ = Balances::slash_reserved(&BOB, 300);
+        assert_ok!(ReceiptsModule::withdraw(Origin::signed(ALICE), BOB));
+        assert_noop!(
+            ReceiptsModule::confirm(Origin::signed(BOB), ALICE),
+            Error::<Test>::InsufficientStashAccount
+        );
+        assert_eq!(Balances::reserved_balance(&BOB), 1200);
+    });
+}
+
+#[test]
+fn test_force_withdraw_after_timeout() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(ALICE, b"USDT", 10_000);
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt,
+            2000,
+            2
+        ));
+        assert_noop!(
+            ReceiptsModule::force_withdraw(Origin::signed(ALICE), BOB),
+            Error::<Test>::ReceiptNotExists
+        );
+        assert_ok!(ReceiptsModule::withdraw(Origin::signed(ALICE), BOB));
+        assert_ok!(ReceiptsModule::withdraw_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt
+        ));
+        run_to_block(10);
+        assert_noop!(
+            ReceiptsModule::force_withdraw(Origin::signed(ALICE), BOB),
+            Error::<Test>::RevokingNotExpired
+        );
+        run_to_block(11);
+        assert_ok!(ReceiptsModule::force_withdraw(Origin::signed(ALICE), BOB));
+        assert_eq!(Balances::free_balance(&ALICE), 1_000_000);
+        assert_eq!(Token::free_balance(&usdt, &ALICE), 10_000);
+        assert_eq!(Balances::reserved_balance(&BOB), 1000);
+        assert_eq!(ReceiptsModule::dominators(&BOB).unwrap().pledged, 1000);
+        assert_eq!(ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Tao), None);
+    });
+}
+
+#[test]
+fn test_force_withdraw_takes_shortfall_from_pledge() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        let _ = Balances::slash_reserved(&BOB, 300);
+        assert_ok!(ReceiptsModule::withdraw(Origin::signed(ALICE), BOB));
+        run_to_block(11);
+        assert_ok!(ReceiptsModule::force_withdraw(Origin::signed(ALICE), BOB));
+        assert_eq!(Balances::free_balance(&ALICE), 1_000_000);
+        assert_eq!(Balances::reserved_balance(&BOB), 700);
+        assert_eq!(ReceiptsModule::dominators(&BOB).unwrap().pledged, 700);
+    });
+}
+
+#[test]
+fn test_quit_dominator_without_receipts() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_eq!(Balances::reserved_balance(&BOB), 1000);
+        assert_ok!(ReceiptsModule::quit_dominator(Origin::signed(BOB)));
+        assert_eq!(Balances::reserved_balance(&BOB), 0);
+        assert_eq!(ReceiptsModule::dominators(&BOB), None);
+        assert_noop!(
+            ReceiptsModule::quit_dominator(Origin::signed(BOB)),
+            Error::<Test>::DominatorNotFound
+        );
+    });
+}
+
+#[test]
+fn test_close_dominator_drops_operators() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::add_operator(Origin::signed(BOB), DAVE));
+        assert_ok!(ReceiptsModule::quit_dominator(Origin::signed(BOB)));
+        assert!(!ReceiptsModule::operators(&BOB, &DAVE));
+        // the dominator claimed again doesn't get its old operators back
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_noop!(
+            ReceiptsModule::sync(Origin::signed(DAVE), ALICE, 0, vec![], [1u8; 32]),
+            Error::<Test>::DominatorNotFound
+        );
+        // and the operator is free to act for another
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(CHRIS), 1000));
+        assert_ok!(ReceiptsModule::add_operator(Origin::signed(CHRIS), DAVE));
+        assert_eq!(ReceiptsModule::operator_of(&DAVE), Some(CHRIS));
+    });
+}
+
+#[test]
+fn test_quit_dominator_after_receipts_cleared() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(CHRIS), BOB, 300, 1));
+        assert_eq!(ReceiptsModule::hostings(&BOB), 2);
+        assert_ok!(ReceiptsModule::quit_dominator(Origin::signed(BOB)));
+        assert_eq!(
+            ReceiptsModule::dominators(&BOB).unwrap().status,
+            DominatorStatus::Closing(1)
+        );
+        assert_noop!(
+            ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 2),
+            Error::<Test>::InvalidStatus
+        );
+
+        assert_ok!(ReceiptsModule::withdraw(Origin::signed(ALICE), BOB));
+        assert_ok!(ReceiptsModule::confirm(Origin::signed(BOB), ALICE));
+        assert_eq!(ReceiptsModule::hostings(&BOB), 1);
+        assert!(ReceiptsModule::dominators(&BOB).is_some());
+
+        // CHRIS never asks for withdrawal, so it is pulled out after the grace period
+        run_to_block(20);
+        assert_noop!(
+            ReceiptsModule::force_withdraw(Origin::signed(CHRIS), BOB),
+            Error::<Test>::ReceiptNotExists
+        );
+        run_to_block(21);
+        assert_ok!(ReceiptsModule::force_withdraw(Origin::signed(CHRIS), BOB));
+        assert_eq!(Balances::free_balance(&CHRIS), 1_000_000);
+        assert_eq!(ReceiptsModule::hostings(&BOB), 0);
+        assert!(ReceiptsModule::dominators(&BOB).is_some());
+        assert_ok!(ReceiptsModule::close_dominator(Origin::signed(CHRIS), BOB));
+        assert_eq!(ReceiptsModule::dominators(&BOB), None);
+        assert_eq!(Balances::reserved_balance(&BOB), 0);
+        assert_eq!(Balances::free_balance(&BOB), 1_000_000);
+    });
+}
+
+#[test]
+fn test_close_dominator() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_noop!(
+            ReceiptsModule::close_dominator(Origin::signed(CHRIS), BOB),
+            Error::<Test>::InvalidStatus
+        );
+        assert_ok!(ReceiptsModule::quit_dominator(Origin::signed(BOB)));
+        assert_noop!(
+            ReceiptsModule::close_dominator(Origin::signed(CHRIS), BOB),
+            Error::<Test>::ReceiptsOutstanding
+        );
+
+        // the last receipt is confirmed without closing the dominator
+        assert_ok!(ReceiptsModule::withdraw(Origin::signed(ALICE), BOB));
+        assert_ok!(ReceiptsModule::confirm(Origin::signed(BOB), ALICE));
+        assert_eq!(ReceiptsModule::hostings(&BOB), 0);
+        assert!(ReceiptsModule::dominators(&BOB).is_some());
+
+        assert_ok!(ReceiptsModule::close_dominator(Origin::signed(CHRIS), BOB));
+        assert_eq!(ReceiptsModule::dominators(&BOB), None);
+        assert_eq!(Balances::reserved_balance(&BOB), 0);
+        assert_noop!(
+            ReceiptsModule::close_dominator(Origin::signed(CHRIS), BOB),
+            Error::<Test>::DominatorNotFound
+        );
+    });
+}
+
+#[test]
+fn test_slash_dominator() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 600, 1));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(CHRIS), BOB, 200, 1));
+        assert_noop!(
+            ReceiptsModule::slash(Origin::signed(ALICE), BOB, 800),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(ReceiptsModule::slash(Origin::root(), BOB, 800));
+        let claimed = ReceiptsModule::dominators(&BOB).unwrap();
+        assert_eq!(claimed.pledged, 200);
+        assert_eq!(claimed.status, DominatorStatus::Banned);
+        assert_eq!(Balances::reserved_balance(&BOB), 1000);
+        // nothing is short, the whole 800 goes to the pot
+        assert_eq!(Balances::free_balance(&ALICE), 999_400);
+        assert_eq!(Balances::free_balance(&CHRIS), 999_800);
+        assert_eq!(ReceiptsModule::insurance_pot(), 800);
+        assert_eq!(ReceiptsModule::slash_pools(&BOB), None);
+        assert_noop!(
+            ReceiptsModule::sync(Origin::signed(BOB), ALICE, 0, vec![], [0u8; 32]),
+            Error::<Test>::DominatorBanned
+        );
+
+        // no need to wait for the timeout
+        assert_ok!(ReceiptsModule::force_withdraw(Origin::signed(ALICE), BOB));
+        assert_eq!(Balances::free_balance(&ALICE), 1_000_000);
+        assert_eq!(Balances::reserved_balance(&BOB), 400);
+    });
+}
+
+#[test]
+fn test_adjust_pledge() {
+    receipts_test_ext().execute_with(|| {
+        assert_noop!(
+            ReceiptsModule::claim_dominator(Origin::signed(BOB), 50),
+            Error::<Test>::PledgeUnsatisfied
+        );
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::add_pledge(Origin::signed(BOB), 500));
+        assert_eq!(ReceiptsModule::dominators(&BOB).unwrap().pledged, 1500);
+        assert_eq!(Balances::reserved_balance(&BOB), 1500);
+
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 5000, 1));
+        // at least a tenth of the hosted 5000
+        assert_noop!(
+            ReceiptsModule::reduce_pledge(Origin::signed(BOB), 1100),
+            Error::<Test>::CollateralInsufficient
+        );
+        assert_ok!(ReceiptsModule::reduce_pledge(Origin::signed(BOB), 1000));
+        assert_eq!(ReceiptsModule::dominators(&BOB).unwrap().pledged, 500);
+        assert_eq!(Balances::reserved_balance(&BOB), 5500);
+        assert_noop!(
+            ReceiptsModule::reduce_pledge(Origin::signed(BOB), 1000),
+            Error::<Test>::PledgeUnsatisfied
+        );
+        assert_noop!(
+            ReceiptsModule::add_pledge(Origin::signed(CHRIS), 500),
+            Error::<Test>::DominatorNotFound
+        );
+    });
+}
+
+#[test]
+fn test_sync_commits_ledger() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_eq!(ReceiptsModule::ledgers(&BOB), None);
+        // nothing is hosted beyond ALICE's receipt
+        assert_noop!(
+            ReceiptsModule::sync(Origin::signed(BOB), ALICE, 400, vec![], [1u8; 32]),
+            Error::<Test>::UnbalancedSync
+        );
+        assert_ok!(ReceiptsModule::sync(
+            Origin::signed(BOB),
+            ALICE,
+            500,
+            vec![],
+            [1u8; 32]
+        ));
+        let first = ReceiptsModule::ledgers(&BOB).unwrap();
+        assert_eq!(
+            first,
+            LedgerCommitment {
+                root: [1u8; 32],
+                seq: 0,
+                prev: Default::default(),
+                block: 1,
+            }
+        );
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Tao),
+            Some(Receipt {
+                value: 500,
+                status: ReceiptStatus::Active
+            })
+        );
+        assert_eq!(
+            last_event(),
+            TestEvent::receipts(RawEvent::LedgerSynced(
+                BOB,
+                ALICE,
+                vec![(AssetId::Tao, 500, 500)],
+                0,
+                [1u8; 32]
+            ))
+        );
+        run_to_block(2);
+        assert_ok!(ReceiptsModule::sync(
+            Origin::signed(BOB),
+            ALICE,
+            500,
+            vec![],
+            [2u8; 32]
+        ));
+        assert_eq!(
+            ReceiptsModule::ledgers(&BOB).unwrap(),
+            LedgerCommitment {
+                root: [2u8; 32],
+                seq: 1,
+                prev: BlakeTwo256::hash_of(&first),
+                block: 2,
+            }
+        );
+    });
+}
+
+fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let mut pair = [a, b];
+    pair.sort_unstable();
+    sp_io::hashing::blake2_256(&pair.concat())
+}
+
+#[test]
+fn test_answer_challenge() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(CHRIS), BOB, 300, 1));
+        assert_noop!(
+            ReceiptsModule::challenge(Origin::signed(ALICE), BOB),
+            Error::<Test>::LedgerNotExists
+        );
+        let alice = ReceiptsModule::ledger_leaf(&ALICE, &BOB);
+        let chris = ReceiptsModule::ledger_leaf(&CHRIS, &BOB);
+        let root = hash_pair(alice, chris);
+        assert_ok!(ReceiptsModule::sync(
+            Origin::signed(BOB),
+            ALICE,
+            500,
+            vec![],
+            root
+        ));
+        assert_noop!(
+            ReceiptsModule::challenge(Origin::signed(DAVE), BOB),
+            Error::<Test>::ReceiptNotExists
+        );
+        // CHRIS's receipts have not been committed by a sync yet
+        assert_noop!(
+            ReceiptsModule::challenge(Origin::signed(CHRIS), BOB),
+            Error::<Test>::LedgerNotExists
+        );
+        assert_ok!(ReceiptsModule::challenge(Origin::signed(ALICE), BOB));
+        assert_eq!(
+            ReceiptsModule::challenges(&BOB, &ALICE),
+            Some(Challenge {
+                deadline: 6,
+                root,
+                leaf: alice,
+                value: 500,
+            })
+        );
+        assert_eq!(Balances::reserved_balance(&ALICE), 10);
+        // the receipts moving afterwards don't change what is to be proved
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 100, 2));
+        assert_noop!(
+            ReceiptsModule::challenge(Origin::signed(ALICE), BOB),
+            Error::<Test>::ChallengeAlreadyExists
+        );
+        assert_noop!(
+            ReceiptsModule::answer(Origin::signed(BOB), ALICE, vec![alice]),
+            Error::<Test>::InvalidProof
+        );
+        assert_ok!(ReceiptsModule::answer(Origin::signed(BOB), ALICE, vec![chris]));
+        assert_eq!(ReceiptsModule::challenges(&BOB, &ALICE), None);
+        assert_eq!(Balances::reserved_balance(&ALICE), 0);
+        assert_eq!(Balances::free_balance(&BOB), 999_010);
+        assert_eq!(ReceiptsModule::challenge_deadlines(6), vec![]);
+
+        // nor does a grant since the last sync
+        assert_ok!(ReceiptsModule::challenge(Origin::signed(ALICE), BOB));
+        assert_ok!(ReceiptsModule::answer(Origin::signed(BOB), ALICE, vec![chris]));
+        assert_eq!(Balances::free_balance(&BOB), 999_020);
+        run_to_block(6);
+        assert_eq!(
+            ReceiptsModule::dominators(&BOB).unwrap().status,
+            DominatorStatus::Active
+        );
+    });
+}
+
+#[test]
+fn test_unanswered_challenge_bans_dominator() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::sync(
+            Origin::signed(BOB),
+            ALICE,
+            500,
+            vec![],
+            [0u8; 32]
+        ));
+        assert_ok!(ReceiptsModule::challenge(Origin::signed(ALICE), BOB));
+        run_to_block(5);
+        assert_eq!(
+            ReceiptsModule::dominators(&BOB).unwrap().status,
+            DominatorStatus::Active
+        );
+        run_to_block(6);
+        let claimed = ReceiptsModule::dominators(&BOB).unwrap();
+        assert_eq!(claimed.status, DominatorStatus::Banned);
+        // slashed by the 500 it failed to prove
+        assert_eq!(claimed.pledged, 500);
+        assert_eq!(ReceiptsModule::challenges(&BOB, &ALICE), None);
+        assert_eq!(Balances::reserved_balance(&ALICE), 0);
+        // ALICE's receipt is fully backed, the slash goes to the pot
+        assert_eq!(Balances::free_balance(&ALICE), 999_500);
+        assert_eq!(ReceiptsModule::insurance_pot(), 500);
+    });
+}
+
+#[test]
+fn test_expired_challenges_per_block() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(CHRIS), BOB, 300, 1));
+        assert_ok!(ReceiptsModule::sync_batch(
+            Origin::signed(BOB),
+            vec![(ALICE, 500, vec![]), (CHRIS, 300, vec![])],
+            [0u8; 32]
+        ));
+        assert_ok!(ReceiptsModule::challenge(Origin::signed(ALICE), BOB));
+        assert_ok!(ReceiptsModule::challenge(Origin::signed(CHRIS), BOB));
+
+        // one challenge per block, the other one is left to the next block
+        run_to_block(6);
+        assert_eq!(ReceiptsModule::challenges(&BOB, &ALICE), None);
+        assert!(ReceiptsModule::challenges(&BOB, &CHRIS).is_some());
+        assert_eq!(ReceiptsModule::challenge_deadlines(7), vec![(BOB, CHRIS)]);
+        assert_eq!(ReceiptsModule::dominators(&BOB).unwrap().pledged, 500);
+
+        run_to_block(7);
+        assert_eq!(ReceiptsModule::challenges(&BOB, &CHRIS), None);
+        assert_eq!(ReceiptsModule::dominators(&BOB).unwrap().pledged, 200);
+        assert_eq!(Balances::reserved_balance(&CHRIS), 0);
+        let events = receipts_events();
+        assert_eq!(
+            events[events.len() - 2..],
+            [
+                RawEvent::ChallengeFailed(BOB, CHRIS),
+                RawEvent::DominatorSlashed(BOB, 300, 0)
+            ]
+        );
+    });
+}
+
+#[test]
+fn test_migrate_receipts_to_v2() {
+    receipts_test_ext().execute_with(|| {
+        let legacy: Vec<LegacyReceipt<u32, u64>> = vec![
+            LegacyReceipt::Tao(500, ReceiptStatus::Active),
+            LegacyReceipt::Token(1, 2000, ReceiptStatus::Revoking(1)),
+        ];
+        let key = Blake2_128Concat::hash(&(ALICE, BOB).encode());
+        put_storage_value(b"Receipts", b"Receipts", &key, legacy);
+        let key = Blake2_128Concat::hash(&(CHRIS, BOB).encode());
+        put_storage_value(
+            b"Receipts",
+            b"Receipts",
+            &key,
+            vec![LegacyReceipt::<u32, u64>::Tao(300, ReceiptStatus::Active)],
+        );
+        let key = Blake2_128Concat::hash(&BOB.encode());
+        put_storage_value(
+            b"Receipts",
+            b"Dominators",
+            &key,
+            LegacyDominator {
+                pledged: 1000,
+                status: LegacyDominatorStatus::Closing,
+            },
+        );
+        StorageVersion::put(Releases::V1);
+
+        ReceiptsModule::on_runtime_upgrade();
+        assert_eq!(ReceiptsModule::storage_version(), Releases::V2);
+        let now = System::block_number();
+        // the grace period starts with the upgrade
+        assert_eq!(
+            ReceiptsModule::dominators(&BOB),
+            Some(Dominator {
+                pledged: 1000,
+                status: DominatorStatus::Closing(now),
+            })
+        );
+        assert_eq!(ReceiptsModule::last_active(&BOB), Some(now));
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Tao),
+            Some(Receipt {
+                value: 500,
+                status: ReceiptStatus::Active
+            })
+        );
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Token(1)),
+            Some(Receipt {
+                value: 2000,
+                status: ReceiptStatus::Revoking(1)
+            })
+        );
+        assert_eq!(
+            ReceiptsModule::receipts((&CHRIS, &BOB), AssetId::Tao),
+            Some(Receipt {
+                value: 300,
+                status: ReceiptStatus::Active
+            })
+        );
+        assert_eq!(ReceiptsModule::hostings(&BOB), 3);
+        assert_eq!(ReceiptsModule::fund_owners(&BOB, &ALICE), 2);
+        assert_eq!(ReceiptsModule::fund_owners(&BOB, &CHRIS), 1);
+        assert_eq!(ReceiptsModule::hosted_tao(&BOB), 800);
+        assert_eq!(ReceiptsModule::receipt_totals(&BOB, AssetId::Tao), 800);
+
+        // runs only once
+        ReceiptsModule::on_runtime_upgrade();
+        assert_eq!(ReceiptsModule::hostings(&BOB), 3);
+        assert_eq!(ReceiptsModule::fund_owners(&BOB, &ALICE), 2);
+    });
+}
+
+#[test]
+fn test_upgrade_fresh_chain() {
+    receipts_test_ext().execute_with(|| {
+        assert_eq!(ReceiptsModule::storage_version(), Releases::V2);
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+
+        ReceiptsModule::on_runtime_upgrade();
+        assert_eq!(ReceiptsModule::hostings(&BOB), 1);
+        assert_eq!(ReceiptsModule::insurance_paid_at(&BOB), Some(1));
+    });
+}
+
+#[test]
+fn test_query_receipts() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(ALICE, b"USDT", 10_000);
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(DAVE), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt,
+            2000,
+            2
+        ));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), DAVE, 100, 3));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(CHRIS), BOB, 300, 4));
+
+        let mut receipts = ReceiptsModule::receipts_of(&ALICE);
+        receipts.sort_by_key(|(d, a, _)| (*d, *a == AssetId::Tao));
+        assert_eq!(
+            receipts,
+            vec![
+                (BOB, AssetId::Token(usdt), Receipt::new(2000)),
+                (BOB, AssetId::Tao, Receipt::new(500)),
+                (DAVE, AssetId::Tao, Receipt::new(100)),
+            ]
+        );
+
+        let mut hosted = ReceiptsModule::hosted_by(&BOB);
+        hosted.sort_by_key(|(o, _, _)| *o);
+        assert_eq!(
+            hosted,
+            vec![(ALICE, 500, vec![(usdt, 2000)]), (CHRIS, 300, vec![])]
+        );
+        assert_eq!(ReceiptsModule::hosted_by(&CHRIS), vec![]);
+
+        // the index follows the receipts as they go
+        assert_ok!(ReceiptsModule::withdraw(Origin::signed(CHRIS), BOB));
+        assert_ok!(ReceiptsModule::confirm(Origin::signed(BOB), CHRIS));
+        assert_eq!(ReceiptsModule::fund_owners(&BOB, &CHRIS), 0);
+        assert_eq!(ReceiptsModule::fund_owners(&BOB, &ALICE), 2);
+        assert_eq!(
+            ReceiptsModule::hosted_by(&BOB),
+            vec![(ALICE, 500, vec![(usdt, 2000)])]
+        );
+    });
+}
+
+#[test]
+fn test_sync_batch() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(ALICE, b"USDT", 10_000);
+        assert_ok!(Token::transfer(Origin::signed(ALICE), usdt, CHRIS, 5_000));
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(CHRIS), BOB, 300, 2));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt,
+            2000,
+            3
+        ));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(CHRIS),
+            BOB,
+            usdt,
+            1000,
+            4
+        ));
+
+        // TAO out of nothing
+        assert_noop!(
+            ReceiptsModule::sync_batch(
+                Origin::signed(BOB),
+                vec![(ALICE, 600, vec![]), (CHRIS, 300, vec![])],
+                [1u8; 32]
+            ),
+            Error::<Test>::UnbalancedSync
+        );
+        assert_noop!(
+            ReceiptsModule::sync_batch(
+                Origin::signed(BOB),
+                vec![(ALICE, 500, vec![]), (ALICE, 500, vec![])],
+                [1u8; 32]
+            ),
+            Error::<Test>::IllegalParameters
+        );
+        // five receipts out of three entries
+        assert_noop!(
+            ReceiptsModule::sync_batch(
+                Origin::signed(BOB),
+                vec![
+                    (ALICE, 500, vec![(usdt, 2000)]),
+                    (CHRIS, 300, vec![(usdt, 1000)]),
+                    (DAVE, 0, vec![])
+                ],
+                [1u8; 32]
+            ),
+            Error::<Test>::BatchTooLarge
+        );
+
+        // ALICE buys 1000 USDT with 200 TAO from CHRIS
+        assert_ok!(ReceiptsModule::sync_batch(
+            Origin::signed(BOB),
+            vec![
+                (ALICE, 300, vec![(usdt, 3000)]),
+                (CHRIS, 500, vec![(usdt, 0)])
+            ],
+            [1u8; 32]
+        ));
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Tao),
+            Some(Receipt::new(300))
+        );
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Token(usdt)),
+            Some(Receipt::new(3000))
+        );
+        assert_eq!(
+            ReceiptsModule::receipts((&CHRIS, &BOB), AssetId::Tao),
+            Some(Receipt::new(500))
+        );
+        assert_eq!(
+            ReceiptsModule::receipts((&CHRIS, &BOB), AssetId::Token(usdt)),
+            Some(Receipt::new(0))
+        );
+        assert_eq!(ReceiptsModule::ledgers(&BOB).unwrap().root, [1u8; 32]);
+    });
+}
+
+#[test]
+fn test_settle_trades() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(CHRIS, b"USDT", 10_000);
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(CHRIS),
+            BOB,
+            usdt,
+            2000,
+            2
+        ));
+        // CHRIS buys 100 TAO at 3 USDT with a fee of 1 TAO
+        let trade = Trade {
+            maker: ALICE,
+            taker: CHRIS,
+            base: AssetId::Tao,
+            quote: AssetId::Token(usdt),
+            side: Side::Buy,
+            price: 3 * PRICE_UNIT,
+            quantity: 100,
+            fee: 1,
+        };
+        assert_ok!(ReceiptsModule::settle_trades(
+            Origin::signed(BOB),
+            vec![trade.clone()]
+        ));
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Tao),
+            Some(Receipt::new(400))
+        );
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Token(usdt)),
+            Some(Receipt::new(300))
+        );
+        assert_eq!(
+            ReceiptsModule::receipts((&CHRIS, &BOB), AssetId::Tao),
+            Some(Receipt::new(99))
+        );
+        assert_eq!(
+            ReceiptsModule::receipts((&CHRIS, &BOB), AssetId::Token(usdt)),
+            Some(Receipt::new(1700))
+        );
+        // the fee is paid out of the hosted TAO rather than booked as a receipt
+        assert_eq!(ReceiptsModule::receipts((&BOB, &BOB), AssetId::Tao), None);
+        assert_eq!(ReceiptsModule::hostings(&BOB), 4);
+        assert_eq!(Balances::free_balance(&BOB), 999_001);
+        assert_eq!(Balances::reserved_balance(&BOB), 1499);
+
+        // the second fill overdraws ALICE, so none of them applies
+        let oversold = Trade {
+            quantity: 500,
+            ..trade
+        };
+        assert_noop!(
+            ReceiptsModule::settle_trades(Origin::signed(BOB), vec![trade, oversold]),
+            Error::<Test>::InsufficientReceipt
+        );
+    });
+}
+
+#[test]
+fn test_duplicated_memo() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(ALICE, b"USDT", 10_000);
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_noop!(
+            ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1),
+            Error::<Test>::DuplicatedMemo
+        );
+        assert_noop!(
+            ReceiptsModule::grant_token(Origin::signed(ALICE), BOB, usdt, 500, 1),
+            Error::<Test>::DuplicatedMemo
+        );
+        // memos are kept per hosting pair
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(CHRIS), BOB, 500, 1));
+        assert_eq!(ReceiptsModule::memos((&ALICE, &BOB), 1), Some(6));
+
+        run_to_block(6);
+        assert_eq!(ReceiptsModule::memos((&ALICE, &BOB), 1), None);
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Tao),
+            Some(Receipt::new(1000))
+        );
+    });
+}
+
+#[test]
+fn test_migrate_receipt() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(ALICE, b"USDT", 10_000);
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(DAVE), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), DAVE, 100, 1));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt,
+            2000,
+            2
+        ));
+        assert_noop!(
+            ReceiptsModule::migrate_receipt(Origin::signed(ALICE), BOB, BOB, AssetId::Tao),
+            Error::<Test>::IllegalParameters
+        );
+        assert_noop!(
+            ReceiptsModule::migrate_receipt(Origin::signed(ALICE), BOB, CHRIS, AssetId::Tao),
+            Error::<Test>::DominatorNotFound
+        );
+
+        assert_ok!(ReceiptsModule::migrate_receipt(
+            Origin::signed(ALICE),
+            BOB,
+            DAVE,
+            AssetId::Tao
+        ));
+        assert_ok!(ReceiptsModule::migrate_receipt(
+            Origin::signed(ALICE),
+            BOB,
+            DAVE,
+            AssetId::Token(usdt)
+        ));
+        assert_eq!(
+            ReceiptsModule::migrations((&ALICE, &BOB), AssetId::Tao),
+            Some(DAVE)
+        );
+
+        // the source dominator confirms, nothing is freed to the fund owner
+        assert_ok!(ReceiptsModule::confirm(Origin::signed(BOB), ALICE));
+        assert_eq!(Balances::free_balance(&ALICE), 999_400);
+        assert_eq!(Balances::reserved_balance(&BOB), 1000);
+        assert_eq!(Balances::reserved_balance(&DAVE), 1600);
+        assert_eq!(Token::reserved_balance(&usdt, &BOB), 0);
+        assert_eq!(Token::reserved_balance(&usdt, &DAVE), 2000);
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &DAVE), AssetId::Tao),
+            Some(Receipt::new(600))
+        );
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &DAVE), AssetId::Token(usdt)),
+            Some(Receipt::new(2000))
+        );
+        assert_eq!(ReceiptsModule::hostings(&BOB), 0);
+        assert_eq!(ReceiptsModule::hostings(&DAVE), 2);
+        assert_eq!(
+            ReceiptsModule::migrations((&ALICE, &BOB), AssetId::Tao),
+            None
+        );
+    });
+}
+
+#[test]
+fn test_migrate_receipt_after_timeout() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(DAVE), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::migrate_receipt(
+            Origin::signed(ALICE),
+            BOB,
+            DAVE,
+            AssetId::Tao
+        ));
+        assert_noop!(
+            ReceiptsModule::force_withdraw(Origin::signed(ALICE), BOB),
+            Error::<Test>::RevokingNotExpired
+        );
+        run_to_block(11);
+        assert_ok!(ReceiptsModule::force_withdraw(Origin::signed(ALICE), BOB));
+        assert_eq!(Balances::free_balance(&ALICE), 999_500);
+        assert_eq!(Balances::reserved_balance(&BOB), 1000);
+        assert_eq!(Balances::reserved_balance(&DAVE), 1500);
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &DAVE), AssetId::Tao),
+            Some(Receipt::new(500))
+        );
+        assert_eq!(ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Tao), None);
+    });
+}
+
+#[test]
+fn test_migrate_empty_receipt() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(DAVE), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(CHRIS), BOB, 100, 1));
+        assert_noop!(
+            ReceiptsModule::migrate_receipt(Origin::signed(DAVE), BOB, DAVE, AssetId::Tao),
+            Error::<Test>::ReceiptNotExists
+        );
+        // ALICE has traded all her TAO away
+        assert_ok!(ReceiptsModule::sync_batch(
+            Origin::signed(BOB),
+            vec![(ALICE, 0, vec![]), (CHRIS, 600, vec![])],
+            [1u8; 32]
+        ));
+        assert_noop!(
+            ReceiptsModule::migrate_receipt(Origin::signed(ALICE), BOB, DAVE, AssetId::Tao),
+            Error::<Test>::InsufficientReceipt
+        );
+        assert_eq!(
+            ReceiptsModule::migrations((&ALICE, &BOB), AssetId::Tao),
+            None
+        );
+    });
+}
+
+#[test]
+fn test_operators() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_noop!(
+            ReceiptsModule::add_operator(Origin::signed(DAVE), CHRIS),
+            Error::<Test>::DominatorNotFound
+        );
+        assert_ok!(ReceiptsModule::add_operator(Origin::signed(BOB), DAVE));
+        assert!(ReceiptsModule::operators(&BOB, &DAVE));
+        assert_eq!(ReceiptsModule::operator_of(&DAVE), Some(BOB));
+        assert_noop!(
+            ReceiptsModule::claim_dominator(Origin::signed(DAVE), 1000),
+            Error::<Test>::OperatorAlreadyExists
+        );
+
+        // the operator acts on behalf of the dominator
+        assert_ok!(ReceiptsModule::sync(
+            Origin::signed(DAVE),
+            ALICE,
+            500,
+            vec![],
+            [1u8; 32]
+        ));
+        assert_eq!(ReceiptsModule::ledgers(&BOB).unwrap().root, [1u8; 32]);
+        assert_ok!(ReceiptsModule::withdraw(Origin::signed(ALICE), BOB));
+        assert_ok!(ReceiptsModule::confirm(Origin::signed(DAVE), ALICE));
+        assert_eq!(Balances::free_balance(&ALICE), 1_000_000);
+
+        // but can't touch the pledge
+        assert_noop!(
+            ReceiptsModule::add_pledge(Origin::signed(DAVE), 100),
+            Error::<Test>::DominatorNotFound
+        );
+        assert_noop!(
+            ReceiptsModule::quit_dominator(Origin::signed(DAVE)),
+            Error::<Test>::DominatorNotFound
+        );
+
+        assert_noop!(
+            ReceiptsModule::remove_operator(Origin::signed(BOB), CHRIS),
+            Error::<Test>::OperatorNotFound
+        );
+        assert_ok!(ReceiptsModule::remove_operator(Origin::signed(BOB), DAVE));
+        assert_noop!(
+            ReceiptsModule::sync(Origin::signed(DAVE), ALICE, 0, vec![], [2u8; 32]),
+            Error::<Test>::DominatorNotFound
+        );
+    });
+}
+
+#[test]
+fn test_dominator_profile() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(ALICE, b"USDT", 10_000);
+        let eth = issue_token(ALICE, b"ETH", 10_000);
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        let profile = DominatorProfile {
+            name: b"bob".to_vec(),
+            url: vec![],
+            contact: [0u8; 32],
+            tao_cap: Some(600),
+            tokens: vec![(usdt, 1000)],
+            maker_fee: Perbill::from_percent(1),
+            taker_fee: Perbill::from_percent(2),
+        };
+        assert_noop!(
+            ReceiptsModule::set_profile(
+                Origin::signed(BOB),
+                DominatorProfile {
+                    name: vec![0u8; 33],
+                    ..profile.clone()
+                }
+            ),
+            Error::<Test>::ProfileTooLarge
+        );
+        assert_ok!(ReceiptsModule::set_profile(
+            Origin::signed(BOB),
+            profile.clone()
+        ));
+        // 10 + 83 bytes
+        assert_eq!(ReceiptsModule::profile_deposits(&BOB), 93);
+        assert_eq!(Balances::reserved_balance(&BOB), 1093);
+
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_noop!(
+            ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 200, 2),
+            Error::<Test>::HostingCapExceeded
+        );
+        assert_noop!(
+            ReceiptsModule::grant_token(Origin::signed(ALICE), BOB, eth, 100, 3),
+            Error::<Test>::TokenNotSupported
+        );
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt,
+            1000,
+            4
+        ));
+        assert_noop!(
+            ReceiptsModule::grant_token(Origin::signed(ALICE), BOB, usdt, 1, 5),
+            Error::<Test>::HostingCapExceeded
+        );
+
+        // a smaller profile releases part of the deposit
+        assert_ok!(ReceiptsModule::set_profile(
+            Origin::signed(BOB),
+            DominatorProfile {
+                name: vec![],
+                ..profile
+            }
+        ));
+        assert_eq!(ReceiptsModule::profile_deposits(&BOB), 90);
+        assert_eq!(Balances::reserved_balance(&BOB), 1590);
+
+        // the deposit is never paid out to the fund owners
+        let _ = Balances::slash_reserved(&BOB, 100);
+        assert_ok!(ReceiptsModule::withdraw(Origin::signed(ALICE), BOB));
+        assert_noop!(
+            ReceiptsModule::confirm(Origin::signed(BOB), ALICE),
+            Error::<Test>::InsufficientStashAccount
+        );
+    });
+}
+
+#[test]
+fn test_inactive_dominator() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(DAVE), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_eq!(ReceiptsModule::last_active(&BOB), Some(1));
+        run_to_block(20);
+        assert_ok!(ReceiptsModule::sync(
+            Origin::signed(DAVE),
+            ALICE,
+            0,
+            vec![],
+            [1u8; 32]
+        ));
+
+        // one check per block, DAVE's is obsolete anyway
+        run_to_block(31);
+        assert_eq!(
+            ReceiptsModule::dominators(&BOB).unwrap().status,
+            DominatorStatus::Inactive
+        );
+        assert_eq!(
+            ReceiptsModule::dominators(&DAVE).unwrap().status,
+            DominatorStatus::Active
+        );
+        assert_noop!(
+            ReceiptsModule::grant_tao(Origin::signed(CHRIS), BOB, 500, 1),
+            Error::<Test>::InvalidStatus
+        );
+
+        // the fund owner doesn't have to wait
+        assert_ok!(ReceiptsModule::force_withdraw(Origin::signed(ALICE), BOB));
+        assert_eq!(Balances::free_balance(&ALICE), 1_000_000);
+
+        assert_ok!(ReceiptsModule::sync(
+            Origin::signed(BOB),
+            ALICE,
+            0,
+            vec![],
+            [1u8; 32]
+        ));
+        // brought back by the next check, queued after the one of DAVE rolled over
+        run_to_block(32);
+        assert_eq!(
+            ReceiptsModule::dominators(&BOB).unwrap().status,
+            DominatorStatus::Inactive
+        );
+        run_to_block(33);
+        assert_eq!(
+            ReceiptsModule::dominators(&BOB).unwrap().status,
+            DominatorStatus::Active
+        );
+        run_to_block(52);
+        assert_eq!(
+            ReceiptsModule::dominators(&DAVE).unwrap().status,
+            DominatorStatus::Inactive
+        );
+    });
+}
+
+#[test]
+fn test_insurance_fee() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(ALICE, b"USDT", 10_000);
+        Prices::set(usdt, Some(2 * PRICE_UNIT));
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt,
+            250,
+            2
+        ));
+        assert_eq!(ReceiptsModule::insurance_paid_at(&BOB), Some(1));
+        run_to_block(20);
+        assert_ok!(ReceiptsModule::sync(
+            Origin::signed(BOB),
+            ALICE,
+            500,
+            vec![(usdt, 250)],
+            [0u8; 32]
+        ));
+        // collected by the liveness checks only
+        assert_eq!(ReceiptsModule::insurance_pot(), 0);
+        // reserved for something else, not hosted
+        assert_ok!(Balances::reserve(&BOB, 100));
+        assert_eq!(ReceiptsModule::hosted_tao(&BOB), 500);
+
+        // 10% of the hosted value, 500 TAO and 250 USDT worth 500, for each of the three periods
+        run_to_block(31);
+        assert_eq!(Balances::free_balance(&BOB), 998_600);
+        assert_eq!(ReceiptsModule::insurance_pot(), 300);
+        assert_eq!(ReceiptsModule::insurance_paid_at(&BOB), Some(31));
+
+        // out of the pledge since the free balance is gone
+        Balances::make_free_balance_be(&BOB, 1);
+        run_to_block(50);
+        assert_eq!(ReceiptsModule::insurance_pot(), 400);
+        assert_eq!(ReceiptsModule::insurance_paid_at(&BOB), Some(41));
+        assert_eq!(ReceiptsModule::dominators(&BOB).unwrap().pledged, 900);
+        assert_eq!(Balances::reserved_balance(&BOB), 1500);
+    });
+}
+
+#[test]
+fn test_claim_compensation() {
+    receipts_test_ext().execute_with(|| {
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 600, 1));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(CHRIS), BOB, 200, 1));
+        // BOB has lost 400 of what it hosts
+        let _ = Balances::slash_reserved(&BOB, 400);
+        Balances::make_free_balance_be(&ReceiptsModule::account_id(), 200);
+        assert_ok!(ReceiptsModule::slash(Origin::root(), BOB, 100));
+        // the slashed 100 is kept for the fund owners, the other 300 is claimable from the pot
+        assert_eq!(
+            ReceiptsModule::slash_pools(&BOB),
+            Some(SlashPool {
+                id: 0,
+                shortfalls: vec![(AssetId::Tao, 400, 800, 400)],
+                owed: 400,
+                funds: 100,
+                compensation: 300,
+            })
+        );
+        assert_eq!(
+            last_event(),
+            TestEvent::receipts(RawEvent::DominatorBanned(BOB, 100, 300))
+        );
+        assert_eq!(Balances::reserved_balance(&BOB), 1400);
+        assert_eq!(Balances::free_balance(&ALICE), 999_400);
+        assert_eq!(ReceiptsModule::insurance_pot(), 200);
+        assert_noop!(
+            ReceiptsModule::claim_compensation(Origin::signed(DAVE), BOB),
+            Error::<Test>::NoCompensation
+        );
+
+        // ALICE takes 600:200 of the pool and the shortfall off its receipt
+        assert_ok!(ReceiptsModule::claim_compensation(Origin::signed(ALICE), BOB));
+        assert_eq!(
+            last_event(),
+            TestEvent::receipts(RawEvent::CompensationClaimed(BOB, ALICE, 275))
+        );
+        assert_eq!(Balances::free_balance(&ALICE), 999_675);
+        assert_eq!(ReceiptsModule::compensations(&BOB, &ALICE), 25);
+        assert_eq!(ReceiptsModule::insurance_pot(), 0);
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Tao),
+            Some(Receipt::new(300))
+        );
+        // nothing more from the pool, and the pot is empty
+        assert_noop!(
+            ReceiptsModule::claim_compensation(Origin::signed(ALICE), BOB),
+            Error::<Test>::InsufficientInsurance
+        );
+
+        assert_ok!(ReceiptsModule::claim_compensation(Origin::signed(CHRIS), BOB));
+        assert_eq!(Balances::free_balance(&CHRIS), 999_825);
+        assert_eq!(ReceiptsModule::compensations(&BOB, &CHRIS), 75);
+        assert_eq!(
+            ReceiptsModule::receipts((&CHRIS, &BOB), AssetId::Tao),
+            Some(Receipt::new(100))
+        );
+        assert_eq!(ReceiptsModule::slash_pools(&BOB).unwrap().owed, 0);
+        // what is left of the receipts is backed
+        assert_eq!(ReceiptsModule::receipt_totals(&BOB, AssetId::Tao), 400);
+        assert_eq!(ReceiptsModule::hosted_tao(&BOB), 400);
+        assert_eq!(Balances::reserved_balance(&BOB), 1300);
+        assert_ok!(ReceiptsModule::force_withdraw(Origin::signed(ALICE), BOB));
+        assert_eq!(Balances::free_balance(&ALICE), 999_975);
+        assert_eq!(ReceiptsModule::dominators(&BOB).unwrap().pledged, 900);
+    });
+}
+
+#[test]
+fn test_compensate_token_holders() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(CHRIS, b"USDT", 10_000);
+        Prices::set(usdt, Some(2 * PRICE_UNIT));
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 400, 1));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(CHRIS),
+            BOB,
+            usdt,
+            100,
+            1
+        ));
+        // BOB is short of 50 USDT, i.e. 100 TAO
+        assert_ok!(Token::unreserve(&usdt, &BOB, 50));
+        assert_ok!(ReceiptsModule::slash(Origin::root(), BOB, 400));
+        // 100 is kept for the USDT short, the other 300 goes to the pot
+        assert_eq!(ReceiptsModule::insurance_pot(), 300);
+        assert_ok!(ReceiptsModule::claim_compensation(Origin::signed(CHRIS), BOB));
+        assert_eq!(Balances::free_balance(&ALICE), 999_600);
+        assert_eq!(Balances::free_balance(&CHRIS), 1_000_100);
+        assert_noop!(
+            ReceiptsModule::claim_compensation(Origin::signed(ALICE), BOB),
+            Error::<Test>::NoCompensation
+        );
+        assert_eq!(
+            ReceiptsModule::receipts((&CHRIS, &BOB), AssetId::Token(usdt)),
+            Some(Receipt::new(50))
+        );
+        assert_eq!(
+            ReceiptsModule::receipts((&ALICE, &BOB), AssetId::Tao),
+            Some(Receipt::new(400))
+        );
+    });
+}
+
+#[test]
+fn test_collateral_ratio() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(ALICE, b"USDT", 100_000);
+        let eth = issue_token(ALICE, b"ETH", 100_000);
+        Prices::set(eth, None);
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_noop!(
+            ReceiptsModule::margin_call(Origin::signed(CHRIS), BOB),
+            Error::<Test>::CollateralSufficient
+        );
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 1000, 1));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt,
+            9000,
+            2
+        ));
+        assert_eq!(ReceiptsModule::hosted_tokens(&BOB).len(), 1);
+        // 10 times the pledge at most
+        assert_noop!(
+            ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 1, 3),
+            Error::<Test>::CollateralInsufficient
+        );
+        assert_noop!(
+            ReceiptsModule::reduce_pledge(Origin::signed(BOB), 1),
+            Error::<Test>::CollateralInsufficient
+        );
+        assert_noop!(
+            ReceiptsModule::grant_token(Origin::signed(ALICE), BOB, eth, 1, 3),
+            Error::<Test>::PriceNotAvailable
+        );
+
+        // USDT doubles, the pledge is restored in time
+        Prices::set(usdt, Some(2 * PRICE_UNIT));
+        assert_ok!(ReceiptsModule::margin_call(Origin::signed(CHRIS), BOB));
+        assert_eq!(ReceiptsModule::margin_calls(&BOB), Some(11));
+        assert_noop!(
+            ReceiptsModule::margin_call(Origin::signed(CHRIS), BOB),
+            Error::<Test>::InvalidStatus
+        );
+        assert_ok!(ReceiptsModule::add_pledge(Origin::signed(BOB), 900));
+        // cleared once the call is due
+        assert_eq!(ReceiptsModule::margin_calls(&BOB), Some(11));
+        run_to_block(11);
+        assert_eq!(ReceiptsModule::margin_calls(&BOB), None);
+        assert_eq!(
+            ReceiptsModule::dominators(&BOB).unwrap().status,
+            DominatorStatus::Active
+        );
+
+        // USDT triples, the dominator is closed after the margin call period
+        Prices::set(usdt, Some(3 * PRICE_UNIT));
+        assert_ok!(ReceiptsModule::margin_call(Origin::signed(CHRIS), BOB));
+        assert_eq!(ReceiptsModule::margin_calls(&BOB), Some(21));
+        run_to_block(20);
+        assert_eq!(
+            ReceiptsModule::dominators(&BOB).unwrap().status,
+            DominatorStatus::Active
+        );
+        run_to_block(21);
+        assert_eq!(
+            ReceiptsModule::dominators(&BOB).unwrap().status,
+            DominatorStatus::Closing(21)
+        );
+        assert_eq!(ReceiptsModule::margin_calls(&BOB), None);
+    });
+}
+
+#[test]
+fn test_unpriced_tokens() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(ALICE, b"USDT", 100_000);
+        let eth = issue_token(ALICE, b"ETH", 100_000);
+        let dot = issue_token(ALICE, b"DOT", 100_000);
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt,
+            7000,
+            1
+        ));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(ALICE),
+            BOB,
+            eth,
+            1000,
+            2
+        ));
+
+        // ETH goes stale at 2 TAO, its last known price still counts
+        Prices::set(eth, Some(2 * PRICE_UNIT));
+        Prices::set(eth, None);
+        assert_ok!(ReceiptsModule::reduce_pledge(Origin::signed(BOB), 100));
+        assert_noop!(
+            ReceiptsModule::reduce_pledge(Origin::signed(BOB), 1),
+            Error::<Test>::CollateralInsufficient
+        );
+        assert_noop!(
+            ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 1, 3),
+            Error::<Test>::CollateralInsufficient
+        );
+
+        // a token never priced can't be taken
+        Prices::set(dot, None);
+        assert_noop!(
+            ReceiptsModule::grant_token(Origin::signed(ALICE), BOB, dot, 1, 4),
+            Error::<Test>::PriceNotAvailable
+        );
+    });
+}
+
+#[test]
+fn test_withdraw_events() {
+    receipts_test_ext().execute_with(|| {
+        let usdt = issue_token(ALICE, b"USDT", 10_000);
+        assert_ok!(ReceiptsModule::claim_dominator(Origin::signed(BOB), 1000));
+        assert_ok!(ReceiptsModule::grant_tao(Origin::signed(ALICE), BOB, 500, 1));
+        assert_ok!(ReceiptsModule::grant_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt,
+            2000,
+            2
+        ));
+        assert_ok!(ReceiptsModule::withdraw(Origin::signed(ALICE), BOB));
+        assert_eq!(
+            last_event(),
+            TestEvent::receipts(RawEvent::TaoWithdrawRequested(ALICE, BOB, 500))
+        );
+        assert_ok!(ReceiptsModule::withdraw_token(
+            Origin::signed(ALICE),
+            BOB,
+            usdt
+        ));
+        assert_eq!(
+            last_event(),
+            TestEvent::receipts(RawEvent::TokenWithdrawRequested(ALICE, BOB, usdt, 2000))
+        );
+        assert_ok!(ReceiptsModule::confirm(Origin::signed(BOB), ALICE));
+        assert_eq!(
+            last_event(),
+            TestEvent::receipts(RawEvent::AssetsClear(
+                ALICE,
+                BOB,
+                Some(500),
+                vec![(usdt, 2000)],
+                vec![]
+            ))
+        );
+
+        // a single event for the slash that bans
+        let before = receipts_events().len();
+        assert_ok!(ReceiptsModule::slash(Origin::root(), BOB, 100));
+        assert_eq!(receipts_events().len(), before + 1);
+        assert_eq!(
+            last_event(),
+            TestEvent::receipts(RawEvent::DominatorBanned(BOB, 100, 0))
+        );
+        assert_ok!(ReceiptsModule::slash(Origin::root(), BOB, 100));
+        assert_eq!(
+            last_event(),
+            TestEvent::receipts(RawEvent::DominatorSlashed(BOB, 100, 0))
+        );
+    });
+}
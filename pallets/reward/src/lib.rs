@@ -13,21 +13,15 @@
 // limitations under the License.
 
 #![cfg_attr(not(feature = "std"), no_std)]
-use codec::Decode;
 use frame_support::{
-    debug, decl_error, decl_event, decl_module, decl_storage, ensure,
-    traits::{BalanceStatus, Currency, Get, ReservableCurrency},
+    decl_error, decl_event, decl_module, decl_storage,
+    traits::{Currency, Get, ReservableCurrency},
     weights::{DispatchClass, Weight},
     Parameter,
 };
-use frame_system::{ensure_root, ensure_signed};
 use fuso_support::traits::ProofOfSecurity;
-use sp_runtime::traits::{
-    AtLeast32BitUnsigned, CheckEqual, CheckedAdd, CheckedSub, Hash, MaybeDisplay,
-    MaybeMallocSizeOf, MaybeSerializeDeserialize, Member,
-};
+use sp_runtime::traits::{MaybeDisplay, MaybeSerializeDeserialize, Member};
 use sp_std::{
-    collections::btree_set::BTreeSet,
     convert::{From, TryInto},
     fmt::Debug,
     prelude::*,
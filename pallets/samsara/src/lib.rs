@@ -233,7 +233,7 @@ impl<T: Trait> Module<T> {
             T::Currency::remove_lock(SAMSARA_ID, &i.0);
         }
 
-        if Self::voter_members().len() > 0 {
+        if !Self::voter_members().is_empty() {
             VoterMembers::<T>::kill();
         }
         if Self::start_vote().is_some() {
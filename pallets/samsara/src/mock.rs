@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+// `impl_outer_origin` implements `Into` rather than `From`
+#![allow(clippy::from_over_into)]
+
 use crate::{Module, Trait};
 use frame_support::traits::{OnFinalize, OnInitialize};
 use frame_support::{
@@ -123,8 +126,7 @@ pub fn run_to_block(block: u64) {
 pub fn samsara_test_ext() -> sp_io::TestExternalities {
     let mut t = system::GenesisConfig::default()
         .build_storage::<Test>()
-        .unwrap()
-        .into();
+        .unwrap();
 
     balances::GenesisConfig::<Test> {
         balances: vec![(ALICE, 60000000000), (BOB, 610000000000)],
@@ -0,0 +1,31 @@
+// Copyright 2021 UINB Technologies Pte. Ltd.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime API definition for the token pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// the lints are raised by the code `decl_runtime_apis` generates
+#![allow(clippy::too_many_arguments, clippy::unnecessary_mut_passed)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+
+sp_api::decl_runtime_apis! {
+    pub trait TokenApi<TokenId> where
+        TokenId: Codec,
+    {
+        /// The token issued with the symbol, case insensitive.
+        fn token_by_symbol(symbol: Vec<u8>) -> Option<TokenId>;
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright 2021 UINB Technologies Pte. Ltd.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! RPC interface for the token pallet.
+
+use codec::Codec;
+pub use fuso_pallet_token_rpc_runtime_api::TokenApi as TokenRuntimeApi;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::sync::Arc;
+
+#[rpc]
+pub trait TokenApi<BlockHash, TokenId> {
+    #[rpc(name = "fuso_token_tokenBySymbol")]
+    fn token_by_symbol(&self, symbol: String, at: Option<BlockHash>) -> Result<Option<TokenId>>;
+}
+
+pub struct Token<C, Block> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<Block>,
+}
+
+impl<C, Block> Token<C, Block> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+const RUNTIME_ERROR: i64 = 1;
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(RUNTIME_ERROR),
+        message: "Unable to query tokens.".into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+impl<C, Block, TokenId> TokenApi<<Block as BlockT>::Hash, TokenId> for Token<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: TokenRuntimeApi<Block, TokenId>,
+    TokenId: Codec,
+{
+    fn token_by_symbol(
+        &self,
+        symbol: String,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<TokenId>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.token_by_symbol(&at, symbol.into_bytes()).map_err(runtime_error)
+    }
+}
@@ -16,16 +16,19 @@
 #![recursion_limit = "256"]
 use ascii::AsciiStr;
 use codec::{Codec, Decode, Encode};
-use frame_support::traits::BalanceStatus;
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, ensure, Parameter};
+use frame_support::traits::{BalanceStatus, EnsureOrigin, Get};
+use frame_support::{
+    decl_error, decl_event, decl_module, decl_storage, ensure, weights::Weight,
+    IterableStorageMap, Parameter,
+};
 use frame_system::ensure_signed;
 use fuso_support::traits::ReservableToken;
 use sp_runtime::traits::{
     AtLeast32BitUnsigned, CheckedAdd, CheckedSub, MaybeSerializeDeserialize, Member, One,
-    StaticLookup, Zero,
+    Saturating, StaticLookup, Zero,
 };
-use sp_runtime::DispatchResult;
-use sp_std::{fmt::Debug, vec::Vec};
+use sp_runtime::{DispatchError, DispatchResult};
+use sp_std::{cell::Cell, fmt::Debug, vec::Vec};
 
 #[derive(Encode, Decode, Clone, PartialEq, Eq, Default, Debug)]
 pub struct TokenAccountData<Balance> {
@@ -33,10 +36,40 @@ pub struct TokenAccountData<Balance> {
     pub reserved: Balance,
 }
 
+/// The longest full name of a token in bytes.
+pub const MAX_NAME_LENGTH: usize = 64;
+
 #[derive(Encode, Decode, Clone, PartialEq, Eq, Default, Debug)]
-pub struct TokenInfo<Balance> {
+pub struct TokenInfo<Balance, AccountId> {
     pub total: Balance,
     pub symbol: Vec<u8>,
+    pub decimals: u8,
+    /// UTF-8 encoded full name.
+    pub name: Vec<u8>,
+    pub issuer: AccountId,
+    /// The issuer can't mint beyond it if set.
+    pub max_supply: Option<Balance>,
+    /// Accounts holding less than it in total are reaped.
+    pub min_balance: Balance,
+}
+
+#[derive(Encode, Decode)]
+struct LegacyTokenInfo<Balance> {
+    total: Balance,
+    symbol: Vec<u8>,
+}
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Releases {
+    V1,
+    V2,
+    V3,
+}
+
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1
+    }
 }
 
 pub trait Trait: frame_system::Trait {
@@ -59,6 +92,9 @@ pub trait Trait: frame_system::Trait {
         + Codec
         + Debug
         + MaybeSerializeDeserialize;
+
+    /// The origin allowed to reserve well-known symbols, e.g. root or the council.
+    type SymbolOrigin: EnsureOrigin<Self::Origin>;
 }
 
 decl_event! {
@@ -72,8 +108,21 @@ decl_event! {
         TokenTransfered(TokenId, AccountId, AccountId, Balance),
         TokenReserved(TokenId, AccountId, Balance),
         TokenUnreserved(TokenId, AccountId, Balance),
+        TokenMinted(TokenId, AccountId, Balance),
         TokenBurned(TokenId, AccountId, Balance),
         TokenRepatriated(TokenId, AccountId, AccountId, Balance),
+        TokenMetadataSet(TokenId, u8, Vec<u8>),
+        /// The allowance of the spender from the owner is set to the balance.
+        Approval(TokenId, AccountId, AccountId, Balance),
+        /// The account is reaped with the dust burned.
+        DustLost(TokenId, AccountId, Balance),
+        AccountFrozen(TokenId, AccountId),
+        AccountThawed(TokenId, AccountId),
+        TokenPaused(TokenId),
+        TokenUnpaused(TokenId),
+        /// The symbol can only be issued by the account.
+        SymbolReserved(Vec<u8>, AccountId),
+        SymbolUnreserved(Vec<u8>),
     }
 }
 
@@ -84,6 +133,14 @@ decl_error! {
         BalanceZero,
         InvalidTokenName,
         InvalidToken,
+        NotIssuer,
+        ExceedMaxSupply,
+        BelowMinBalance,
+        SymbolTaken,
+        SymbolReserved,
+        InsufficientAllowance,
+        Frozen,
+        Paused,
         InsufficientBalance,
         Overflow,
     }
@@ -95,9 +152,26 @@ decl_storage! {
             (T::TokenId, T::AccountId) => TokenAccountData<T::Balance>;
 
         Tokens get(fn get_token_info): map hasher(twox_64_concat)
-            T::TokenId => TokenInfo<T::Balance>;
+            T::TokenId => TokenInfo<T::Balance, T::AccountId>;
 
         NextTokenId get(fn next_token_id): T::TokenId = Zero::zero();
+
+        Allowances get(fn allowance): map hasher(blake2_128_concat)
+            (T::TokenId, T::AccountId, T::AccountId) => T::Balance;
+
+        Frozen get(fn is_frozen): map hasher(blake2_128_concat)
+            (T::TokenId, T::AccountId) => bool;
+
+        Paused get(fn is_paused): map hasher(twox_64_concat) T::TokenId => bool;
+
+        /// Tokens by their upper case symbols.
+        Symbols get(fn symbols): map hasher(blake2_128_concat) Vec<u8> => Option<T::TokenId>;
+
+        /// Well-known upper case symbols with the only account allowed to issue them.
+        ReservedSymbols get(fn reserved_symbols): map hasher(blake2_128_concat)
+            Vec<u8> => Option<T::AccountId>;
+
+        StorageVersion get(fn storage_version) build(|_| Releases::V3): Releases;
     }
 }
 
@@ -107,30 +181,134 @@ decl_module! {
 
         fn deposit_event() = default;
 
+        fn on_runtime_upgrade() -> Weight {
+            let mut weight = 0;
+            if Self::storage_version() == Releases::V1 {
+                weight = Self::migrate_to_v2();
+            }
+            if Self::storage_version() == Releases::V2 {
+                weight = weight.saturating_add(Self::migrate_to_v3());
+            }
+            weight
+        }
+
         #[weight = 10_000]
-        fn issue(origin, #[compact] total: T::Balance, symbol: Vec<u8>) {
+        pub fn issue(origin,
+                     #[compact] total: T::Balance,
+                     symbol: Vec<u8>,
+                     decimals: u8,
+                     name: Vec<u8>,
+                     max_supply: Option<T::Balance>,
+                     min_balance: T::Balance) {
             let origin = ensure_signed(origin)?;
             ensure!(!total.is_zero(), Error::<T>::AmountZero);
-            let name = AsciiStr::from_ascii(&symbol);
-            ensure!(name.is_ok(), Error::<T>::InvalidTokenName);
-            let name = name.unwrap();
-            ensure!(name.len() >= 2 && name.len() <= 5, Error::<T>::InvalidTokenName);
+            ensure!(max_supply.map(|max| total <= max).unwrap_or(true), Error::<T>::ExceedMaxSupply);
+            ensure!(total >= min_balance, Error::<T>::BelowMinBalance);
+            let key = Self::normalize_symbol(&symbol)?;
+            ensure!(!Symbols::<T>::contains_key(&key), Error::<T>::SymbolTaken);
+            ensure!(
+                Self::reserved_symbols(&key).map(|who| who == origin).unwrap_or(true),
+                Error::<T>::SymbolReserved
+            );
+            Self::ensure_name(&name)?;
             let id = Self::next_token_id();
             NextTokenId::<T>::mutate(|id| *id += One::one());
+            ReservedSymbols::<T>::remove(&key);
+            Symbols::<T>::insert(&key, id);
             // let token_address = <T as Trait>::Hashing::hash(&id.to_ne_bytes());
             Balances::<T>::insert((id, &origin), TokenAccountData {
                 free: total,
                 reserved: Zero::zero(),
             });
             Tokens::<T>::insert(id, TokenInfo {
-                total: total,
-                symbol: symbol,
+                total,
+                symbol,
+                decimals,
+                name,
+                issuer: origin.clone(),
+                max_supply,
+                min_balance,
             });
             Self::deposit_event(RawEvent::TokenIssued(id, origin, total));
         }
 
+        #[weight = 10_000]
+        pub fn mint(origin,
+            token: T::TokenId,
+            to: <T::Lookup as StaticLookup>::Source,
+            #[compact] amount: T::Balance,
+        ) {
+            let origin = ensure_signed(origin)?;
+            ensure!(!amount.is_zero(), Error::<T>::AmountZero);
+            let to = T::Lookup::lookup(to)?;
+            ensure!(!Self::is_paused(&token), Error::<T>::Paused);
+            ensure!(!Self::is_frozen((&token, &to)), Error::<T>::Frozen);
+            Tokens::<T>::try_mutate_exists(&token, |info| -> DispatchResult {
+                let info = info.as_mut().ok_or(Error::<T>::InvalidToken)?;
+                ensure!(info.issuer == origin, Error::<T>::NotIssuer);
+                let total = info.total.checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+                ensure!(info.max_supply.map(|max| total <= max).unwrap_or(true), Error::<T>::ExceedMaxSupply);
+                <Balances<T>>::try_mutate((&token, &to), |account| -> DispatchResult {
+                    account.free = account
+                        .free
+                        .checked_add(&amount)
+                        .ok_or(Error::<T>::Overflow)?;
+                    ensure!(
+                        account.free.saturating_add(account.reserved) >= info.min_balance,
+                        Error::<T>::BelowMinBalance
+                    );
+                    Ok(())
+                })?;
+                info.total = total;
+                Ok(())
+            })?;
+            Self::deposit_event(RawEvent::TokenMinted(token, to, amount));
+        }
+
+        #[weight = 10_000]
+        pub fn burn(origin,
+            token: T::TokenId,
+            from: <T::Lookup as StaticLookup>::Source,
+            #[compact] amount: T::Balance,
+        ) {
+            let origin = ensure_signed(origin)?;
+            ensure!(!amount.is_zero(), Error::<T>::AmountZero);
+            let from = T::Lookup::lookup(from)?;
+            let dust = Tokens::<T>::try_mutate_exists(&token, |info| -> Result<_, DispatchError> {
+                let info = info.as_mut().ok_or(Error::<T>::InvalidToken)?;
+                ensure!(info.issuer == origin, Error::<T>::NotIssuer);
+                let dust = <Balances<T>>::try_mutate_exists((&token, &from), |account| -> Result<_, DispatchError> {
+                    ensure!(account.is_some(), Error::<T>::BalanceZero);
+                    let mut data = account.take().unwrap();
+                    data.free = data
+                        .free
+                        .checked_sub(&amount)
+                        .ok_or(Error::<T>::InsufficientBalance)?;
+                    Ok(Self::settle(data, info.min_balance, account))
+                })?;
+                info.total = info.total.saturating_sub(amount);
+                Ok(dust)
+            })?;
+            Self::deposit_event(RawEvent::TokenBurned(token, from.clone(), amount));
+            Self::burn_dust(&token, &from, dust);
+        }
+
+        #[weight = 10_000]
+        pub fn set_metadata(origin, token: T::TokenId, decimals: u8, name: Vec<u8>) {
+            let origin = ensure_signed(origin)?;
+            Self::ensure_name(&name)?;
+            Tokens::<T>::try_mutate_exists(&token, |info| -> DispatchResult {
+                let info = info.as_mut().ok_or(Error::<T>::InvalidToken)?;
+                ensure!(info.issuer == origin, Error::<T>::NotIssuer);
+                info.decimals = decimals;
+                info.name = name.clone();
+                Ok(())
+            })?;
+            Self::deposit_event(RawEvent::TokenMetadataSet(token, decimals, name));
+        }
+
         #[weight = 0]
-        fn transfer(origin,
+        pub fn transfer(origin,
             token: T::TokenId,
             target: <T::Lookup as StaticLookup>::Source,
             #[compact] amount: T::Balance,
@@ -138,21 +316,151 @@ decl_module! {
             let origin = ensure_signed(origin)?;
             ensure!(!amount.is_zero(), Error::<T>::AmountZero);
             let target = T::Lookup::lookup(target)?;
-            <Balances<T>>::try_mutate_exists((&token, &origin), |from| -> DispatchResult {
-                ensure!(from.is_some(), Error::<T>::BalanceZero);
-                let mut account = from.take().unwrap();
+            Self::do_transfer(&token, &origin, &target, amount)?;
+            Self::deposit_event(RawEvent::TokenTransfered(token, origin, target, amount));
+        }
+
+        #[weight = 10_000]
+        pub fn freeze_account(origin, token: T::TokenId, who: <T::Lookup as StaticLookup>::Source) {
+            let origin = ensure_signed(origin)?;
+            let who = T::Lookup::lookup(who)?;
+            Self::ensure_issuer(&token, &origin)?;
+            Frozen::<T>::insert((&token, &who), true);
+            Self::deposit_event(RawEvent::AccountFrozen(token, who));
+        }
+
+        #[weight = 10_000]
+        pub fn thaw_account(origin, token: T::TokenId, who: <T::Lookup as StaticLookup>::Source) {
+            let origin = ensure_signed(origin)?;
+            let who = T::Lookup::lookup(who)?;
+            Self::ensure_issuer(&token, &origin)?;
+            Frozen::<T>::remove((&token, &who));
+            Self::deposit_event(RawEvent::AccountThawed(token, who));
+        }
+
+        #[weight = 10_000]
+        pub fn pause(origin, token: T::TokenId) {
+            let origin = ensure_signed(origin)?;
+            Self::ensure_issuer(&token, &origin)?;
+            Paused::<T>::insert(&token, true);
+            Self::deposit_event(RawEvent::TokenPaused(token));
+        }
+
+        #[weight = 10_000]
+        pub fn unpause(origin, token: T::TokenId) {
+            let origin = ensure_signed(origin)?;
+            Self::ensure_issuer(&token, &origin)?;
+            Paused::<T>::remove(&token);
+            Self::deposit_event(RawEvent::TokenUnpaused(token));
+        }
+
+        #[weight = 10_000]
+        pub fn reserve_symbol(origin, symbol: Vec<u8>, issuer: T::AccountId) {
+            T::SymbolOrigin::ensure_origin(origin)?;
+            let symbol = Self::normalize_symbol(&symbol)?;
+            ensure!(!Symbols::<T>::contains_key(&symbol), Error::<T>::SymbolTaken);
+            ReservedSymbols::<T>::insert(&symbol, &issuer);
+            Self::deposit_event(RawEvent::SymbolReserved(symbol, issuer));
+        }
+
+        #[weight = 10_000]
+        pub fn unreserve_symbol(origin, symbol: Vec<u8>) {
+            T::SymbolOrigin::ensure_origin(origin)?;
+            let symbol = Self::normalize_symbol(&symbol)?;
+            ReservedSymbols::<T>::remove(&symbol);
+            Self::deposit_event(RawEvent::SymbolUnreserved(symbol));
+        }
+
+        #[weight = 10_000]
+        pub fn approve(origin,
+            token: T::TokenId,
+            spender: <T::Lookup as StaticLookup>::Source,
+            #[compact] amount: T::Balance,
+        ) {
+            let origin = ensure_signed(origin)?;
+            let spender = T::Lookup::lookup(spender)?;
+            Self::set_allowance(&token, &origin, &spender, |_| Ok(amount))?;
+        }
+
+        #[weight = 10_000]
+        pub fn increase_allowance(origin,
+            token: T::TokenId,
+            spender: <T::Lookup as StaticLookup>::Source,
+            #[compact] amount: T::Balance,
+        ) {
+            let origin = ensure_signed(origin)?;
+            let spender = T::Lookup::lookup(spender)?;
+            Self::set_allowance(&token, &origin, &spender, |allowance| {
+                allowance.checked_add(&amount).ok_or(Error::<T>::Overflow)
+            })?;
+        }
+
+        #[weight = 10_000]
+        pub fn decrease_allowance(origin,
+            token: T::TokenId,
+            spender: <T::Lookup as StaticLookup>::Source,
+            #[compact] amount: T::Balance,
+        ) {
+            let origin = ensure_signed(origin)?;
+            let spender = T::Lookup::lookup(spender)?;
+            Self::set_allowance(&token, &origin, &spender, |allowance| {
+                allowance.checked_sub(&amount).ok_or(Error::<T>::InsufficientAllowance)
+            })?;
+        }
+
+        #[weight = 10_000]
+        pub fn transfer_from(origin,
+            token: T::TokenId,
+            owner: <T::Lookup as StaticLookup>::Source,
+            target: <T::Lookup as StaticLookup>::Source,
+            #[compact] amount: T::Balance,
+        ) {
+            let spender = ensure_signed(origin)?;
+            ensure!(!amount.is_zero(), Error::<T>::AmountZero);
+            let owner = T::Lookup::lookup(owner)?;
+            let target = T::Lookup::lookup(target)?;
+            <Allowances<T>>::try_mutate_exists((&token, &owner, &spender), |allowance| -> DispatchResult {
+                let left = allowance
+                    .unwrap_or(Zero::zero())
+                    .checked_sub(&amount)
+                    .ok_or(Error::<T>::InsufficientAllowance)?;
+                *allowance = Some(left).filter(|a| !a.is_zero());
+                Self::do_transfer(&token, &owner, &target, amount)
+            })?;
+            Self::deposit_event(RawEvent::TokenTransfered(token, owner, target, amount));
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    fn do_transfer(
+        token: &T::TokenId,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance,
+    ) -> DispatchResult {
+        Self::ensure_liquid(token, from)?;
+        ensure!(!Self::is_frozen((token, to)), Error::<T>::Frozen);
+        if from == to {
+            ensure!(
+                Self::free_balance(token, from) >= amount,
+                Error::<T>::InsufficientBalance
+            );
+            return Ok(());
+        }
+        let min_balance = Self::get_token_info(token).min_balance;
+        let dust = <Balances<T>>::try_mutate_exists(
+            (token, from),
+            |source| -> Result<T::Balance, DispatchError> {
+                ensure!(source.is_some(), Error::<T>::BalanceZero);
+                let mut account = source.take().unwrap();
                 account.free = account
                     .free
                     .checked_sub(&amount)
                     .ok_or(Error::<T>::InsufficientBalance)?;
-                match account.free == Zero::zero() && account.reserved == Zero::zero() {
-                    true => {}
-                    false => {
-                        from.replace(account);
-                    }
-                }
-                <Balances<T>>::try_mutate_exists((&token, &target), |to| -> DispatchResult {
-                    let mut account = to.take().unwrap_or(TokenAccountData {
+                let dust = Self::settle(account, min_balance, source);
+                <Balances<T>>::try_mutate_exists((token, to), |dest| -> DispatchResult {
+                    let mut account = dest.take().unwrap_or(TokenAccountData {
                         free: Zero::zero(),
                         reserved: Zero::zero(),
                     });
@@ -160,17 +468,145 @@ decl_module! {
                         .free
                         .checked_add(&amount)
                         .ok_or(Error::<T>::Overflow)?;
-                    to.replace(account);
+                    ensure!(
+                        account.free.saturating_add(account.reserved) >= min_balance,
+                        Error::<T>::BelowMinBalance
+                    );
+                    dest.replace(account);
                     Ok(())
                 })?;
-                Ok(())
-            })?;
-            Self::deposit_event(RawEvent::TokenTransfered(token, origin, target, amount));
+                Ok(dust)
+            },
+        )?;
+        Self::burn_dust(token, from, dust);
+        Ok(())
+    }
+
+    /// Put the account back unless it is empty or holds less than `min_balance` in total, in
+    /// which case it is reaped and the dust returned. Accounts with a reserved balance are kept
+    /// since the reserve is still owed to whoever reserved it.
+    fn settle(
+        account: TokenAccountData<T::Balance>,
+        min_balance: T::Balance,
+        entry: &mut Option<TokenAccountData<T::Balance>>,
+    ) -> T::Balance {
+        let total = account.free.saturating_add(account.reserved);
+        if total.is_zero() {
+            Zero::zero()
+        } else if total < min_balance && account.reserved.is_zero() {
+            total
+        } else {
+            entry.replace(account);
+            Zero::zero()
         }
     }
-}
 
-impl<T: Trait> Module<T> {}
+    fn burn_dust(token: &T::TokenId, who: &T::AccountId, dust: T::Balance) {
+        if dust.is_zero() {
+            return;
+        }
+        Tokens::<T>::mutate_exists(token, |info| {
+            if let Some(info) = info {
+                info.total = info.total.saturating_sub(dust);
+            }
+        });
+        Self::deposit_event(RawEvent::DustLost(*token, who.clone(), dust));
+    }
+
+    fn set_allowance(
+        token: &T::TokenId,
+        owner: &T::AccountId,
+        spender: &T::AccountId,
+        f: impl FnOnce(T::Balance) -> Result<T::Balance, Error<T>>,
+    ) -> DispatchResult {
+        ensure!(Tokens::<T>::contains_key(token), Error::<T>::InvalidToken);
+        let allowance = f(Self::allowance((token, owner, spender)))?;
+        if allowance.is_zero() {
+            <Allowances<T>>::remove((token, owner, spender));
+        } else {
+            <Allowances<T>>::insert((token, owner, spender), allowance);
+        }
+        Self::deposit_event(RawEvent::Approval(
+            *token,
+            owner.clone(),
+            spender.clone(),
+            allowance,
+        ));
+        Ok(())
+    }
+
+    fn ensure_issuer(token: &T::TokenId, who: &T::AccountId) -> DispatchResult {
+        ensure!(Tokens::<T>::contains_key(token), Error::<T>::InvalidToken);
+        ensure!(&Self::get_token_info(token).issuer == who, Error::<T>::NotIssuer);
+        Ok(())
+    }
+
+    /// The balance of a frozen account or paused token can't be moved or reserved, but what is
+    /// already reserved can still be released.
+    fn ensure_liquid(token: &T::TokenId, who: &T::AccountId) -> DispatchResult {
+        ensure!(!Self::is_paused(token), Error::<T>::Paused);
+        ensure!(!Self::is_frozen((token, who)), Error::<T>::Frozen);
+        Ok(())
+    }
+
+    /// The token issued with the symbol, case insensitive.
+    pub fn token_by_symbol(symbol: &[u8]) -> Option<T::TokenId> {
+        Self::symbols(Self::upper(symbol))
+    }
+
+    fn upper(symbol: &[u8]) -> Vec<u8> {
+        symbol.iter().map(u8::to_ascii_uppercase).collect()
+    }
+
+    /// Validate the symbol and return its upper case as the key of the registry.
+    fn normalize_symbol(symbol: &[u8]) -> Result<Vec<u8>, DispatchError> {
+        let ascii = AsciiStr::from_ascii(symbol).map_err(|_| Error::<T>::InvalidTokenName)?;
+        ensure!(ascii.len() >= 2 && ascii.len() <= 5, Error::<T>::InvalidTokenName);
+        Ok(Self::upper(symbol))
+    }
+
+    fn ensure_name(name: &[u8]) -> DispatchResult {
+        ensure!(name.len() <= MAX_NAME_LENGTH, Error::<T>::InvalidTokenName);
+        ensure!(sp_std::str::from_utf8(name).is_ok(), Error::<T>::InvalidTokenName);
+        Ok(())
+    }
+
+    /// Tokens issued before the metadata take their symbol as name, no decimals and the default
+    /// account as issuer, so their metadata can't be changed.
+    fn migrate_to_v2() -> Weight {
+        let count = Cell::<Weight>::new(0);
+        Tokens::<T>::translate::<LegacyTokenInfo<T::Balance>, _>(|_, old| {
+            count.set(count.get().saturating_add(1));
+            Some(TokenInfo {
+                total: old.total,
+                name: old.symbol.clone(),
+                symbol: old.symbol,
+                decimals: 0,
+                issuer: Default::default(),
+                max_supply: None,
+                min_balance: Zero::zero(),
+            })
+        });
+        StorageVersion::put(Releases::V2);
+        let count = count.get();
+        T::DbWeight::get().reads_writes(count.saturating_add(1), count.saturating_add(1))
+    }
+
+    /// Index the symbols of the issued tokens, the earliest token keeps a duplicated symbol.
+    fn migrate_to_v3() -> Weight {
+        let mut count: Weight = 0;
+        for (id, info) in Tokens::<T>::iter() {
+            count = count.saturating_add(1);
+            Symbols::<T>::mutate(Self::upper(&info.symbol), |indexed| {
+                if indexed.map_or(true, |earlier| id < earlier) {
+                    *indexed = Some(id);
+                }
+            });
+        }
+        StorageVersion::put(Releases::V3);
+        T::DbWeight::get().reads_writes(count.saturating_mul(2), count.saturating_add(1))
+    }
+}
 
 impl<T: Trait> ReservableToken<T::TokenId, T::AccountId> for Module<T> {
     type Balance = T::Balance;
@@ -191,6 +627,9 @@ impl<T: Trait> ReservableToken<T::TokenId, T::AccountId> for Module<T> {
         if value.is_zero() {
             return true;
         }
+        if Self::ensure_liquid(token, who).is_err() {
+            return false;
+        }
         if !<Balances<T>>::contains_key((token, who)) {
             return false;
         }
@@ -204,6 +643,7 @@ impl<T: Trait> ReservableToken<T::TokenId, T::AccountId> for Module<T> {
         if value.is_zero() {
             return Ok(());
         }
+        Self::ensure_liquid(token, who)?;
         <Balances<T>>::try_mutate_exists((token, who), |account| -> DispatchResult {
             ensure!(account.is_some(), Error::<T>::BalanceZero);
             let account = account.as_mut().unwrap();
@@ -217,7 +657,7 @@ impl<T: Trait> ReservableToken<T::TokenId, T::AccountId> for Module<T> {
                 .ok_or(Error::<T>::Overflow)?;
             Ok(())
         })?;
-        Self::deposit_event(RawEvent::TokenReserved(token.clone(), who.clone(), value));
+        Self::deposit_event(RawEvent::TokenReserved(*token, who.clone(), value));
         Ok(())
     }
 
@@ -238,7 +678,7 @@ impl<T: Trait> ReservableToken<T::TokenId, T::AccountId> for Module<T> {
                 .ok_or(Error::<T>::Overflow)?;
             Ok(())
         })?;
-        Self::deposit_event(RawEvent::TokenUnreserved(token.clone(), who.clone(), value));
+        Self::deposit_event(RawEvent::TokenUnreserved(*token, who.clone(), value));
         Ok(())
     }
 
@@ -252,63 +692,78 @@ impl<T: Trait> ReservableToken<T::TokenId, T::AccountId> for Module<T> {
         if slashed == beneficiary {
             return match status {
                 BalanceStatus::Free => Self::unreserve(token, slashed, value),
-                BalanceStatus::Reserved => Self::reserve(token, slashed, value),
+                BalanceStatus::Reserved => {
+                    ensure!(
+                        Self::get_token_balance((token, slashed)).reserved >= value,
+                        Error::<T>::InsufficientBalance
+                    );
+                    Ok(())
+                }
             };
         }
-        <Balances<T>>::try_mutate_exists((token, slashed), |from| -> DispatchResult {
-            ensure!(from.is_some(), Error::<T>::BalanceZero);
-            let mut account = from.take().unwrap();
-            account.reserved = account
-                .reserved
-                .checked_sub(&value)
-                .ok_or(Error::<T>::InsufficientBalance)?;
-            // drop the `from` if dead
-            match account.reserved == Zero::zero() && account.free == Zero::zero() {
-                true => {}
-                false => {
-                    from.replace(account);
-                }
-            }
-            <Balances<T>>::try_mutate_exists((token, beneficiary), |to| -> DispatchResult {
-                let mut account = to.take().unwrap_or(TokenAccountData {
-                    free: Zero::zero(),
-                    reserved: Zero::zero(),
-                });
-                match status {
-                    BalanceStatus::Free => {
-                        account.free = account
-                            .free
-                            .checked_add(&value)
-                            .ok_or(Error::<T>::Overflow)?;
-                    }
-                    BalanceStatus::Reserved => {
-                        account.reserved = account
-                            .reserved
-                            .checked_add(&value)
-                            .ok_or(Error::<T>::Overflow)?;
+        let min_balance = Self::get_token_info(token).min_balance;
+        let dust = <Balances<T>>::try_mutate_exists(
+            (token, slashed),
+            |from| -> Result<T::Balance, DispatchError> {
+                ensure!(from.is_some(), Error::<T>::BalanceZero);
+                let mut account = from.take().unwrap();
+                account.reserved = account
+                    .reserved
+                    .checked_sub(&value)
+                    .ok_or(Error::<T>::InsufficientBalance)?;
+                // drop the `from` if dead or dust
+                let dust = Self::settle(account, min_balance, from);
+                <Balances<T>>::try_mutate_exists((token, beneficiary), |to| -> DispatchResult {
+                    let mut account = to.take().unwrap_or(TokenAccountData {
+                        free: Zero::zero(),
+                        reserved: Zero::zero(),
+                    });
+                    match status {
+                        BalanceStatus::Free => {
+                            account.free = account
+                                .free
+                                .checked_add(&value)
+                                .ok_or(Error::<T>::Overflow)?;
+                            ensure!(
+                                account.free.saturating_add(account.reserved) >= min_balance,
+                                Error::<T>::BelowMinBalance
+                            );
+                        }
+                        BalanceStatus::Reserved => {
+                            account.reserved = account
+                                .reserved
+                                .checked_add(&value)
+                                .ok_or(Error::<T>::Overflow)?;
+                        }
                     }
-                }
-                to.replace(account);
-                Ok(())
-            })?;
-            Ok(())
-        })?;
+                    to.replace(account);
+                    Ok(())
+                })?;
+                Ok(dust)
+            },
+        )?;
         Self::deposit_event(RawEvent::TokenRepatriated(
-            token.clone(),
+            *token,
             slashed.clone(),
             beneficiary.clone(),
             value,
         ));
+        Self::burn_dust(token, slashed, dust);
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    // `impl_outer_origin` implements `Into` rather than `From`
+    #![allow(clippy::from_over_into)]
+
     use super::*;
 
     use frame_support::{
-        assert_noop, assert_ok, impl_outer_origin, parameter_types, weights::Weight,
+        assert_noop, assert_ok, impl_outer_origin, parameter_types,
+        storage::migration::put_storage_value, traits::OnRuntimeUpgrade, weights::Weight,
+        StorageHasher, Twox64Concat,
     };
     use sp_core::H256;
     use sp_runtime::{
@@ -362,14 +817,19 @@ mod tests {
         type Balance = u128;
 
         type TokenId = u32;
+
+        type SymbolOrigin = frame_system::EnsureRoot<u64>;
     }
     type Token = Module<Test>;
 
     fn new_test_ext() -> sp_io::TestExternalities {
-        frame_system::GenesisConfig::default()
+        let mut t = frame_system::GenesisConfig::default()
             .build_storage::<Test>()
-            .unwrap()
-            .into()
+            .unwrap();
+        GenesisConfig::default()
+            .assimilate_storage(&mut t)
+            .unwrap();
+        t.into()
     }
 
     #[test]
@@ -378,7 +838,11 @@ mod tests {
             assert_ok!(Token::issue(
                 Origin::signed(1),
                 1000000,
-                br#"USDT"#.to_vec()
+                br#"USDT"#.to_vec(),
+                6,
+                b"Tether USD".to_vec(),
+                None,
+                0
             ));
             let id = 0u32;
             assert_eq!(
@@ -386,6 +850,11 @@ mod tests {
                 TokenInfo {
                     total: 1000000,
                     symbol: br#"USDT"#.to_vec(),
+                    decimals: 6,
+                    name: b"Tether USD".to_vec(),
+                    issuer: 1,
+                    max_supply: None,
+                    min_balance: 0,
                 }
             );
             assert_eq!(
@@ -395,7 +864,7 @@ mod tests {
                     reserved: Zero::zero(),
                 }
             );
-            assert_ok!(Token::transfer(Origin::signed(1), id.clone(), 2, 1000000));
+            assert_ok!(Token::transfer(Origin::signed(1), id, 2, 1000000));
             assert_eq!(
                 Token::get_token_balance((&id, &1)),
                 TokenAccountData {
@@ -419,7 +888,11 @@ mod tests {
             assert_ok!(Token::issue(
                 Origin::signed(1),
                 1000000,
-                br#"USDT"#.to_vec()
+                br#"USDT"#.to_vec(),
+                6,
+                b"Tether USD".to_vec(),
+                None,
+                0
             ));
             // let id = <Test as Trait>::Hashing::hash(&0u32.to_ne_bytes());
             let id = 0u32;
@@ -460,7 +933,7 @@ mod tests {
                     reserved: 500000,
                 }
             );
-            assert_ok!(Token::transfer(Origin::signed(1), id.clone(), 2, 1));
+            assert_ok!(Token::transfer(Origin::signed(1), id, 2, 1));
             assert_ok!(Token::repatriate_reserved(
                 &id,
                 &1,
@@ -488,4 +961,406 @@ mod tests {
             );
         });
     }
+
+    #[test]
+    fn set_metadata_should_work() {
+        new_test_ext().execute_with(|| {
+            assert_noop!(
+                Token::issue(
+                    Origin::signed(1),
+                    1000000,
+                    br#"USDT"#.to_vec(),
+                    6,
+                    vec![0xff, 0xfe],
+                    None,
+                    0
+                ),
+                Error::<Test>::InvalidTokenName
+            );
+            assert_ok!(Token::issue(
+                Origin::signed(1),
+                1000000,
+                br#"USDT"#.to_vec(),
+                6,
+                b"Tether".to_vec(),
+                None,
+                0
+            ));
+            let id = 0u32;
+            assert_noop!(
+                Token::set_metadata(Origin::signed(2), id, 18, b"Tether USD".to_vec()),
+                Error::<Test>::NotIssuer
+            );
+            assert_noop!(
+                Token::set_metadata(Origin::signed(1), id, 18, vec![b'x'; MAX_NAME_LENGTH + 1]),
+                Error::<Test>::InvalidTokenName
+            );
+            assert_noop!(
+                Token::set_metadata(Origin::signed(1), 1, 18, b"Tether USD".to_vec()),
+                Error::<Test>::InvalidToken
+            );
+            assert_ok!(Token::set_metadata(
+                Origin::signed(1),
+                id,
+                18,
+                "Tether 美元".as_bytes().to_vec()
+            ));
+            let info = Token::get_token_info(&id);
+            assert_eq!(info.decimals, 18);
+            assert_eq!(info.name, "Tether 美元".as_bytes().to_vec());
+            assert_eq!(info.symbol, br#"USDT"#.to_vec());
+        });
+    }
+
+    #[test]
+    fn migrate_tokens_to_v2() {
+        new_test_ext().execute_with(|| {
+            let id = 0u32;
+            put_storage_value(
+                b"Tokens",
+                b"Tokens",
+                &Twox64Concat::hash(&id.encode()),
+                LegacyTokenInfo::<u128> {
+                    total: 1000000,
+                    symbol: br#"USDT"#.to_vec(),
+                },
+            );
+            assert_eq!(Token::storage_version(), Releases::V3);
+            StorageVersion::put(Releases::V1);
+            Token::on_runtime_upgrade();
+            assert_eq!(Token::storage_version(), Releases::V3);
+            assert_eq!(Token::token_by_symbol(b"usdt"), Some(id));
+            assert_eq!(
+                Token::get_token_info(&id),
+                TokenInfo {
+                    total: 1000000,
+                    symbol: br#"USDT"#.to_vec(),
+                    decimals: 0,
+                    name: br#"USDT"#.to_vec(),
+                    issuer: 0,
+                    max_supply: None,
+                    min_balance: 0,
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn mint_and_burn_should_work() {
+        new_test_ext().execute_with(|| {
+            assert_noop!(
+                Token::issue(
+                    Origin::signed(1),
+                    1000,
+                    br#"USDT"#.to_vec(),
+                    6,
+                    vec![],
+                    Some(999),
+                    0
+                ),
+                Error::<Test>::ExceedMaxSupply
+            );
+            assert_ok!(Token::issue(
+                Origin::signed(1),
+                1000,
+                br#"USDT"#.to_vec(),
+                6,
+                vec![],
+                Some(1500),
+                0
+            ));
+            let id = 0u32;
+            assert_noop!(
+                Token::mint(Origin::signed(2), id, 2, 100),
+                Error::<Test>::NotIssuer
+            );
+            assert_ok!(Token::mint(Origin::signed(1), id, 2, 500));
+            assert_eq!(Token::free_balance(&id, &2), 500);
+            assert_eq!(Token::total_issuance(&id), 1500);
+            assert_noop!(
+                Token::mint(Origin::signed(1), id, 2, 1),
+                Error::<Test>::ExceedMaxSupply
+            );
+
+            assert_noop!(
+                Token::burn(Origin::signed(2), id, 2, 100),
+                Error::<Test>::NotIssuer
+            );
+            assert_noop!(
+                Token::burn(Origin::signed(1), id, 2, 501),
+                Error::<Test>::InsufficientBalance
+            );
+            assert_ok!(Token::burn(Origin::signed(1), id, 2, 500));
+            assert_eq!(Token::total_issuance(&id), 1000);
+            assert!(!Balances::<Test>::contains_key((&id, &2)));
+            assert_noop!(
+                Token::burn(Origin::signed(1), id, 2, 1),
+                Error::<Test>::BalanceZero
+            );
+            // the burnt supply can be minted again
+            assert_ok!(Token::mint(Origin::signed(1), id, 3, 500));
+            assert_eq!(Token::total_issuance(&id), 1500);
+        });
+    }
+
+    #[test]
+    fn allowances_should_work() {
+        new_test_ext().execute_with(|| {
+            assert_noop!(
+                Token::approve(Origin::signed(1), 0, 2, 100),
+                Error::<Test>::InvalidToken
+            );
+            assert_ok!(Token::issue(
+                Origin::signed(1),
+                1000,
+                br#"USDT"#.to_vec(),
+                6,
+                vec![],
+                None,
+                0
+            ));
+            let id = 0u32;
+            assert_ok!(Token::approve(Origin::signed(1), id, 2, 100));
+            assert_eq!(Token::allowance((id, 1, 2)), 100);
+            assert_ok!(Token::increase_allowance(Origin::signed(1), id, 2, 50));
+            assert_eq!(Token::allowance((id, 1, 2)), 150);
+            assert_noop!(
+                Token::decrease_allowance(Origin::signed(1), id, 2, 151),
+                Error::<Test>::InsufficientAllowance
+            );
+            assert_ok!(Token::decrease_allowance(Origin::signed(1), id, 2, 30));
+            assert_eq!(Token::allowance((id, 1, 2)), 120);
+
+            assert_noop!(
+                Token::transfer_from(Origin::signed(3), id, 1, 3, 1),
+                Error::<Test>::InsufficientAllowance
+            );
+            assert_noop!(
+                Token::transfer_from(Origin::signed(2), id, 1, 3, 121),
+                Error::<Test>::InsufficientAllowance
+            );
+            assert_ok!(Token::transfer_from(Origin::signed(2), id, 1, 3, 100));
+            assert_eq!(Token::free_balance(&id, &1), 900);
+            assert_eq!(Token::free_balance(&id, &3), 100);
+            assert_eq!(Token::allowance((id, 1, 2)), 20);
+
+            // the allowance is kept if the owner can't afford it
+            assert_ok!(Token::transfer(Origin::signed(1), id, 3, 900));
+            assert_noop!(
+                Token::transfer_from(Origin::signed(2), id, 1, 3, 20),
+                Error::<Test>::BalanceZero
+            );
+            assert_eq!(Token::allowance((id, 1, 2)), 20);
+            assert_ok!(Token::approve(Origin::signed(1), id, 2, 0));
+            assert!(!Allowances::<Test>::contains_key((id, 1, 2)));
+        });
+    }
+
+    #[test]
+    fn freeze_and_pause_should_work() {
+        new_test_ext().execute_with(|| {
+            assert_ok!(Token::issue(
+                Origin::signed(1),
+                1000,
+                br#"USDT"#.to_vec(),
+                6,
+                vec![],
+                None,
+                0
+            ));
+            let id = 0u32;
+            assert_ok!(Token::transfer(Origin::signed(1), id, 2, 500));
+            assert_ok!(Token::reserve(&id, &2, 100));
+            assert_noop!(
+                Token::freeze_account(Origin::signed(2), id, 2),
+                Error::<Test>::NotIssuer
+            );
+            assert_ok!(Token::freeze_account(Origin::signed(1), id, 2));
+            assert!(Token::is_frozen((id, 2)));
+            assert_noop!(
+                Token::transfer(Origin::signed(2), id, 3, 100),
+                Error::<Test>::Frozen
+            );
+            assert_noop!(
+                Token::transfer(Origin::signed(1), id, 2, 100),
+                Error::<Test>::Frozen
+            );
+            assert_ok!(Token::approve(Origin::signed(2), id, 3, 100));
+            assert_noop!(
+                Token::transfer_from(Origin::signed(3), id, 2, 3, 100),
+                Error::<Test>::Frozen
+            );
+            assert!(!Token::can_reserve(&id, &2, 100));
+            assert_noop!(Token::reserve(&id, &2, 100), Error::<Test>::Frozen);
+            assert_noop!(
+                Token::mint(Origin::signed(1), id, 2, 100),
+                Error::<Test>::Frozen
+            );
+            // the reserved balance of a frozen account can still be released
+            assert_ok!(Token::unreserve(&id, &2, 50));
+            assert_ok!(Token::repatriate_reserved(
+                &id,
+                &2,
+                &3,
+                50,
+                BalanceStatus::Free
+            ));
+            assert_eq!(Token::free_balance(&id, &3), 50);
+            assert_ok!(Token::thaw_account(Origin::signed(1), id, 2));
+            assert_ok!(Token::transfer(Origin::signed(2), id, 3, 50));
+
+            assert_ok!(Token::pause(Origin::signed(1), id));
+            assert!(Token::is_paused(id));
+            assert_noop!(
+                Token::transfer(Origin::signed(1), id, 3, 100),
+                Error::<Test>::Paused
+            );
+            assert_noop!(Token::reserve(&id, &1, 100), Error::<Test>::Paused);
+            assert_noop!(
+                Token::mint(Origin::signed(1), id, 3, 100),
+                Error::<Test>::Paused
+            );
+            assert_ok!(Token::unpause(Origin::signed(1), id));
+            assert_ok!(Token::transfer(Origin::signed(1), id, 3, 100));
+            assert_eq!(Token::free_balance(&id, &3), 200);
+        });
+    }
+
+    #[test]
+    fn dust_should_be_reaped() {
+        new_test_ext().execute_with(|| {
+            assert_noop!(
+                Token::issue(
+                    Origin::signed(1),
+                    10,
+                    br#"USDT"#.to_vec(),
+                    6,
+                    vec![],
+                    None,
+                    100
+                ),
+                Error::<Test>::BelowMinBalance
+            );
+            assert_ok!(Token::issue(
+                Origin::signed(1),
+                1000,
+                br#"USDT"#.to_vec(),
+                6,
+                vec![],
+                None,
+                100
+            ));
+            let id = 0u32;
+            assert_noop!(
+                Token::transfer(Origin::signed(1), id, 2, 99),
+                Error::<Test>::BelowMinBalance
+            );
+            assert_ok!(Token::transfer(Origin::signed(1), id, 2, 150));
+            assert_ok!(Token::transfer(Origin::signed(2), id, 3, 100));
+            assert!(!Balances::<Test>::contains_key((id, 2)));
+            assert_eq!(Token::get_token_info(&id).total, 950);
+
+            // the reserved balance keeps the account alive
+            assert_ok!(Token::reserve(&id, &3, 50));
+            assert_ok!(Token::transfer(Origin::signed(3), id, 1, 50));
+            assert_eq!(Token::get_token_balance((&id, &3)).reserved, 50);
+            assert_ok!(Token::repatriate_reserved(
+                &id,
+                &3,
+                &1,
+                50,
+                BalanceStatus::Free
+            ));
+            assert!(!Balances::<Test>::contains_key((id, 3)));
+            assert_eq!(Token::free_balance(&id, &1), 950);
+            assert_eq!(Token::get_token_info(&id).total, 950);
+
+            // nor can the reserve be freed as dust to a new account
+            assert_ok!(Token::reserve(&id, &1, 150));
+            assert_noop!(
+                Token::repatriate_reserved(&id, &1, &4, 99, BalanceStatus::Free),
+                Error::<Test>::BelowMinBalance
+            );
+            assert_ok!(Token::repatriate_reserved(
+                &id,
+                &1,
+                &4,
+                100,
+                BalanceStatus::Free
+            ));
+            assert_eq!(Token::free_balance(&id, &4), 100);
+        });
+    }
+
+    #[test]
+    fn symbols_should_be_unique() {
+        new_test_ext().execute_with(|| {
+            assert_ok!(Token::issue(
+                Origin::signed(1),
+                1000,
+                br#"USDT"#.to_vec(),
+                6,
+                vec![],
+                None,
+                0
+            ));
+            assert_eq!(Token::token_by_symbol(b"UsdT"), Some(0));
+            assert_eq!(Token::token_by_symbol(b"ETH"), None);
+            assert_noop!(
+                Token::issue(
+                    Origin::signed(2),
+                    1000,
+                    br#"usdt"#.to_vec(),
+                    6,
+                    vec![],
+                    None,
+                    0
+                ),
+                Error::<Test>::SymbolTaken
+            );
+
+            assert!(Token::reserve_symbol(Origin::signed(1), b"eth".to_vec(), 2).is_err());
+            assert_noop!(
+                Token::reserve_symbol(Origin::root(), b"usdt".to_vec(), 2),
+                Error::<Test>::SymbolTaken
+            );
+            assert_ok!(Token::reserve_symbol(Origin::root(), b"eth".to_vec(), 2));
+            assert_eq!(Token::reserved_symbols(b"ETH".to_vec()), Some(2));
+            assert_noop!(
+                Token::issue(
+                    Origin::signed(1),
+                    1000,
+                    br#"ETH"#.to_vec(),
+                    18,
+                    vec![],
+                    None,
+                    0
+                ),
+                Error::<Test>::SymbolReserved
+            );
+            assert_ok!(Token::issue(
+                Origin::signed(2),
+                1000,
+                br#"Eth"#.to_vec(),
+                18,
+                vec![],
+                None,
+                0
+            ));
+            assert_eq!(Token::token_by_symbol(b"eth"), Some(1));
+            assert_eq!(Token::reserved_symbols(b"ETH".to_vec()), None);
+
+            assert_ok!(Token::reserve_symbol(Origin::root(), b"DOT".to_vec(), 2));
+            assert_ok!(Token::unreserve_symbol(Origin::root(), b"dot".to_vec()));
+            assert_ok!(Token::issue(
+                Origin::signed(1),
+                1000,
+                br#"DOT"#.to_vec(),
+                10,
+                vec![],
+                None,
+                0
+            ));
+        });
+    }
 }
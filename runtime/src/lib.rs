@@ -14,6 +14,8 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![recursion_limit = "256"]
+// `construct_runtime` implements `Into` rather than `From`
+#![allow(clippy::from_over_into)]
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 use frame_support::traits::{Currency, OnUnbalanced};
@@ -28,7 +30,7 @@ use sp_runtime::traits::{
 use sp_runtime::{
     create_runtime_str, generic, impl_opaque_keys,
     transaction_validity::{TransactionSource, TransactionValidity},
-    ApplyExtrinsicResult, MultiSignature,
+    ApplyExtrinsicResult, ModuleId, MultiSignature,
 };
 use sp_std::prelude::*;
 #[cfg(feature = "std")]
@@ -67,6 +69,9 @@ pub type AccountIndex = u32;
 /// Balance of an account.
 pub type Balance = u128;
 
+/// Identifier of a token.
+pub type TokenId = u32;
+
 /// Index of a transaction in the chain.
 pub type Index = u32;
 
@@ -104,10 +109,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: create_runtime_str!("fusotao"),
     impl_name: create_runtime_str!("fusotao"),
     authoring_version: 1,
-    spec_version: 100,
+    spec_version: 101,
     impl_version: 1,
     apis: RUNTIME_API_VERSIONS,
-    transaction_version: 1,
+    transaction_version: 2,
 };
 
 pub const MILLISECS_PER_BLOCK: u64 = 6000;
@@ -261,7 +266,7 @@ impl pallet_balances::Trait for Runtime {
 
 parameter_types! {
     pub const VotePeriod: BlockNumber = 14 * DAYS;
-    pub const MinimumVotingLock: Balance = 1 * DOLLARS;
+    pub const MinimumVotingLock: Balance = DOLLARS;
 }
 
 impl fuso_pallet_elections::Trait for Runtime {
@@ -355,14 +360,78 @@ impl pallet_sudo::Trait for Runtime {
 impl fuso_pallet_token::Trait for Runtime {
     type Event = Event;
     type Balance = Balance;
-    type TokenId = u32;
+    type TokenId = TokenId;
+    type SymbolOrigin = frame_system::EnsureRoot<AccountId>;
+}
+
+parameter_types! {
+    pub const RevokeTimeout: BlockNumber = 3 * DAYS;
+    pub const ClosingGracePeriod: BlockNumber = 7 * DAYS;
+    pub const MinimumPledge: Balance = 1_000 * DOLLARS;
+    pub const ChallengeWindow: BlockNumber = DAYS;
+    pub const ChallengeDeposit: Balance = 10 * DOLLARS;
+    pub const MaxChallengesPerBlock: u32 = 16;
+    pub const MaxSyncBatch: u32 = 1024;
+    pub const MemoExpiry: BlockNumber = 7 * DAYS;
+    pub const MaxProfileLength: u32 = 128;
+    pub const MaxSupportedTokens: u32 = 64;
+    pub const ProfileDepositBase: Balance = 10 * DOLLARS;
+    pub const ProfileDepositPerByte: Balance = CENTS;
+    pub const LivenessWindow: BlockNumber = DAYS;
+    pub const MaxLivenessChecks: u32 = 64;
+    pub const ReceiptsModuleId: ModuleId = ModuleId(*b"fuso/rcp");
+    pub const InsuranceFeeRate: Perbill = Perbill::from_perthousand(1);
+    pub const InsurancePeriod: BlockNumber = 30 * DAYS;
+    pub const MaxCompensationPerIncident: Balance = 100_000 * DOLLARS;
+    pub const PledgeMultiple: u32 = 10;
+    pub const MarginCallPeriod: BlockNumber = 3 * DAYS;
 }
 
 impl fuso_pallet_receipts::Trait for Runtime {
     type Event = Event;
     type Currency = Balances;
-    type TokenId = u32;
+    type TokenId = TokenId;
     type Token = Token;
+    type RevokeTimeout = RevokeTimeout;
+    type ClosingGracePeriod = ClosingGracePeriod;
+    type SlashOrigin = frame_system::EnsureRoot<AccountId>;
+    type Slash = Receipts;
+    type MinimumPledge = MinimumPledge;
+    type ChallengeWindow = ChallengeWindow;
+    type ChallengeDeposit = ChallengeDeposit;
+    type MaxChallengesPerBlock = MaxChallengesPerBlock;
+    type MaxSyncBatch = MaxSyncBatch;
+    type MemoExpiry = MemoExpiry;
+    type MaxProfileLength = MaxProfileLength;
+    type MaxSupportedTokens = MaxSupportedTokens;
+    type ProfileDepositBase = ProfileDepositBase;
+    type ProfileDepositPerByte = ProfileDepositPerByte;
+    type LivenessWindow = LivenessWindow;
+    type MaxLivenessChecks = MaxLivenessChecks;
+    type ModuleId = ReceiptsModuleId;
+    type InsuranceFeeRate = InsuranceFeeRate;
+    type InsurancePeriod = InsurancePeriod;
+    type MaxCompensationPerIncident = MaxCompensationPerIncident;
+    type Prices = Oracle;
+    type PledgeMultiple = PledgeMultiple;
+    type MarginCallPeriod = MarginCallPeriod;
+}
+
+parameter_types! {
+    pub const AggregationWindow: BlockNumber = 10 * MINUTES;
+    pub const StaleAfter: BlockNumber = HOURS;
+    pub const MinFeeds: u32 = 3;
+    pub const OracleMaxTokens: u32 = 256;
+}
+
+impl fuso_pallet_oracle::Trait for Runtime {
+    type Event = Event;
+    type TokenId = TokenId;
+    type Feeders = Council;
+    type AggregationWindow = AggregationWindow;
+    type StaleAfter = StaleAfter;
+    type MinFeeds = MinFeeds;
+    type MaxTokens = OracleMaxTokens;
 }
 
 parameter_types! {
@@ -412,11 +481,12 @@ construct_runtime!(
         Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
         TransactionPayment: pallet_transaction_payment::{Module, Storage},
         Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
-        Receipts: fuso_pallet_receipts::{Module, Call, Storage, Event<T>},
-        Token: fuso_pallet_token::{Module, Call, Storage, Event<T>},
+        Receipts: fuso_pallet_receipts::{Module, Call, Storage, Config, Event<T>},
+        Token: fuso_pallet_token::{Module, Call, Storage, Config, Event<T>},
         Elections: fuso_pallet_elections::{Module, Event<T>},
         Foundation: fuso_pallet_foundation::{Module, Call, Storage, Config<T>, Event<T>},
         Samsara: fuso_pallet_samsara::{Module, Call, Storage, Event<T>},
+        Oracle: fuso_pallet_oracle::{Module, Call, Storage, Event<T>},
     }
 );
 
@@ -580,4 +650,35 @@ impl_runtime_apis! {
         }
     }
 
+    impl fuso_pallet_receipts_rpc_runtime_api::ReceiptsApi<Block, AccountId, TokenId, BlockNumber>
+        for Runtime {
+        fn receipts_of(
+            fund_owner: AccountId,
+        ) -> Vec<(
+            AccountId,
+            fuso_pallet_receipts::AssetId<TokenId>,
+            fuso_pallet_receipts::Receipt<BlockNumber>,
+        )> {
+            Receipts::receipts_of(&fund_owner)
+        }
+
+        fn hosted_by(dominator: AccountId) -> Vec<fuso_pallet_receipts::LedgerEntry<AccountId, TokenId>> {
+            Receipts::hosted_by(&dominator)
+        }
+
+        fn dominator(dominator: AccountId) -> Option<fuso_pallet_receipts::Dominator<BlockNumber>> {
+            Receipts::dominators(&dominator)
+        }
+
+        fn insurance_pot() -> Balance {
+            Receipts::insurance_pot()
+        }
+    }
+
+    impl fuso_pallet_token_rpc_runtime_api::TokenApi<Block, TokenId> for Runtime {
+        fn token_by_symbol(symbol: Vec<u8>) -> Option<TokenId> {
+            Token::token_by_symbol(&symbol)
+        }
+    }
+
 }